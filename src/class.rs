@@ -13,6 +13,89 @@ pub unsafe trait ScriptClass: Sized {
     const NAME: &'static str;
 }
 
+/// A trait for native classes defined in Rust that extend another native class defined in Rust.
+/// The subclass embeds its base as the first field, which lets methods exported for the base be
+/// called on instances of the subclass, both from scripts and from Rust.
+///
+/// The subclass should be exported with its base set through
+/// [`ClassExportBuilder::base_class`](crate::ClassExportBuilder::base_class). The base does not
+/// have to appear before the subclass in [`exports!`](crate::exports), exports are registered in
+/// the order of their dependencies.
+///
+/// # Example
+/// ```rust
+/// use red4ext_rs::types::IScriptable;
+/// use red4ext_rs::{ClassExport, ScriptClass, Subclass, class_kind, exports, methods};
+///
+/// #[derive(Debug, Default, Clone)]
+/// #[repr(C)]
+/// struct Animal {
+///     base: IScriptable,
+///     legs: i32,
+/// }
+///
+/// impl Animal {
+///     fn legs(&self) -> i32 {
+///         self.legs
+///     }
+/// }
+///
+/// unsafe impl ScriptClass for Animal {
+///     const NAME: &'static str = "Animal";
+///     type Kind = class_kind::Native;
+/// }
+///
+/// #[derive(Debug, Default, Clone)]
+/// #[repr(C)]
+/// struct Dog {
+///     base: Animal,
+///     good: bool,
+/// }
+///
+/// impl Dog {
+///     fn describe(&self) -> String {
+///         format!("a dog with {} legs", self.as_base().legs())
+///     }
+/// }
+///
+/// unsafe impl ScriptClass for Dog {
+///     const NAME: &'static str = "Dog";
+///     type Kind = class_kind::Native;
+/// }
+///
+/// unsafe impl Subclass for Dog {
+///     type Base = Animal;
+/// }
+///
+/// let exports = exports![
+///     ClassExport::<Dog>::builder()
+///         .base_class::<Animal>()
+///         .methods(methods![c"Describe" => Dog::describe])
+///         .build(),
+///     ClassExport::<Animal>::builder()
+///         .methods(methods![c"Legs" => Animal::legs])
+///         .build(),
+/// ];
+/// ```
+///
+/// # Safety
+/// Implementors must be `#[repr(C)]` and have a field of type [`Subclass::Base`] at offset zero.
+pub unsafe trait Subclass: ScriptClass<Kind = class_kind::Native> {
+    type Base: ScriptClass<Kind = class_kind::Native>;
+
+    /// Returns a reference to the embedded base.
+    #[inline]
+    fn as_base(&self) -> &Self::Base {
+        unsafe { &*(self as *const Self).cast::<Self::Base>() }
+    }
+
+    /// Returns a mutable reference to the embedded base.
+    #[inline]
+    fn as_base_mut(&mut self) -> &mut Self::Base {
+        unsafe { &mut *(self as *mut Self).cast::<Self::Base>() }
+    }
+}
+
 /// A trait for distinguishing between native and scripted classes.
 #[sealed]
 pub trait ClassKind<T> {
@@ -0,0 +1,267 @@
+//! Typed plugin configuration, available with the `config` feature.
+//!
+//! Plugins opt into a configuration by implementing [`PluginWithConfig`] next to
+//! [`Plugin`](crate::Plugin). The configuration is loaded with
+//! [`PluginConfigOps::load_config`], usually from
+//! [`Plugin::try_on_load`](crate::Plugin::try_on_load) so that the plugin refuses to load if the
+//! configuration is invalid, and can then be retrieved anywhere with
+//! [`PluginConfigOps::config`]. It's usually read from a [`ConfigFile`], which is resolved
+//! relative to the directory of the plugin. If the file does not exist, the default
+//! configuration is written to it.
+//!
+//! A configuration file can also set the filter applied to the log records of the plugin, with
+//! a top-level `log_filter` key using the syntax of [`LogFilter`], e.g.
+//! `log_filter = "info,my_plugin::net=trace"`. The key does not need to be declared by the
+//! configuration type.
+//!
+//! # Example
+//! ```rust
+//! use red4ext_rs::config::{
+//!     ConfigError, ConfigFile, PluginConfig, PluginConfigOps, PluginWithConfig,
+//! };
+//! use red4ext_rs::{Plugin, SdkEnv, SemVer, U16CStr, cargo_semver, wcstr};
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Debug, Default, Serialize, Deserialize)]
+//! #[serde(default)]
+//! struct Settings {
+//!     enabled: bool,
+//!     volume: f32,
+//! }
+//!
+//! impl PluginConfig for Settings {
+//!     fn load(env: &SdkEnv) -> Result<Self, ConfigError> {
+//!         ConfigFile::toml("settings.toml").read(env)
+//!     }
+//! }
+//!
+//! struct Example;
+//!
+//! impl Plugin for Example {
+//!     const AUTHOR: &'static U16CStr = wcstr!("me");
+//!     const NAME: &'static U16CStr = wcstr!("example");
+//!     const VERSION: SemVer = cargo_semver!();
+//!
+//!     fn try_on_load(env: &SdkEnv) -> Result<(), Box<dyn std::error::Error>> {
+//!         let settings = Self::load_config()?;
+//!         env.info(format_args!("loaded settings: {settings:?}"));
+//!         Ok(())
+//!     }
+//! }
+//!
+//! impl PluginWithConfig for Example {
+//!     type Config = Settings;
+//! }
+//! ```
+use std::any::Any;
+use std::marker::PhantomData;
+use std::path::PathBuf;
+use std::{fmt, fs, io};
+
+use sealed::sealed;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use thiserror::Error;
+
+use crate::{LogFilter, LogFilterParseError, Plugin, PluginOps, SdkEnv};
+
+/// The top-level key of a configuration file holding the log filter of the plugin.
+const LOG_FILTER_KEY: &str = "log_filter";
+
+/// A type-erased configuration stored in [`SdkEnv`].
+pub(crate) type AnyConfig = Box<dyn Any + Send + Sync>;
+
+/// A trait for the configuration of a plugin, declared with [`PluginWithConfig::Config`].
+pub trait PluginConfig: Sized + Send + Sync + 'static {
+    /// Loads the configuration, usually by reading a [`ConfigFile`].
+    fn load(env: &SdkEnv) -> Result<Self, ConfigError>;
+}
+
+/// No configuration.
+impl PluginConfig for () {
+    #[inline]
+    fn load(_env: &SdkEnv) -> Result<Self, ConfigError> {
+        Ok(())
+    }
+}
+
+/// A plugin with a typed configuration.
+pub trait PluginWithConfig: Plugin {
+    /// The configuration of the plugin.
+    type Config: PluginConfig;
+}
+
+/// Operations to load and retrieve the configuration of a [`PluginWithConfig`].
+#[sealed]
+pub trait PluginConfigOps: PluginWithConfig {
+    /// Loads the configuration of the plugin, or returns it if it has already been loaded.
+    fn load_config() -> Result<&'static Self::Config, ConfigError>;
+
+    /// Retrieves the configuration of the plugin.
+    ///
+    /// # Panics
+    /// Panics if the configuration hasn't been loaded with
+    /// [`load_config`](Self::load_config) yet.
+    fn config() -> &'static Self::Config;
+}
+
+#[sealed]
+impl<P> PluginConfigOps for P
+where
+    P: PluginWithConfig,
+{
+    fn load_config() -> Result<&'static Self::Config, ConfigError> {
+        let env = Self::env();
+        if env.config.get().is_none() {
+            let config = <Self::Config as PluginConfig>::load(env)?;
+            // another thread may have won the race, in which case its configuration is kept
+            let _ = env.config.set(Box::new(config));
+        }
+        Ok(Self::config())
+    }
+
+    fn config() -> &'static Self::Config {
+        Self::env()
+            .config
+            .get()
+            .and_then(|config| config.downcast_ref())
+            .expect("plugin configuration should be loaded")
+    }
+}
+
+/// The format of a configuration file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Toml,
+    Json,
+}
+
+/// A configuration file of type `C`, located in the directory of the plugin.
+pub struct ConfigFile<C> {
+    name: &'static str,
+    format: ConfigFormat,
+    _phantom: PhantomData<fn() -> C>,
+}
+
+impl<C> ConfigFile<C> {
+    /// Creates a configuration file with the given name and format.
+    #[inline]
+    pub const fn new(name: &'static str, format: ConfigFormat) -> Self {
+        Self {
+            name,
+            format,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Creates a TOML configuration file with the given name.
+    #[inline]
+    pub const fn toml(name: &'static str) -> Self {
+        Self::new(name, ConfigFormat::Toml)
+    }
+
+    /// Creates a JSON configuration file with the given name.
+    #[inline]
+    pub const fn json(name: &'static str) -> Self {
+        Self::new(name, ConfigFormat::Json)
+    }
+
+    /// Returns the full path of the configuration file.
+    pub fn path(&self, env: &SdkEnv) -> io::Result<PathBuf> {
+        Ok(env.plugin_dir()?.join(self.name))
+    }
+}
+
+impl<C> ConfigFile<C>
+where
+    C: Serialize + DeserializeOwned + Default,
+{
+    /// Reads the configuration file. If the file does not exist, the default configuration is
+    /// written to it and returned. If the file has a `log_filter` key, the filter is applied
+    /// with [`SdkEnv::set_log_filter`].
+    pub fn read(&self, env: &SdkEnv) -> Result<C, ConfigError> {
+        let path = self.path(env)?;
+        match fs::read_to_string(&path) {
+            Ok(contents) => {
+                let config = self.deserialize(&contents)?;
+                if let Some(filter) = self.log_filter(&contents)? {
+                    env.set_log_filter(filter);
+                }
+                Ok(config)
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                let config = C::default();
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(&path, self.serialize(&config)?)?;
+                Ok(config)
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn deserialize(&self, contents: &str) -> Result<C, ConfigError> {
+        match self.format {
+            ConfigFormat::Toml => Ok(toml::from_str(contents)?),
+            ConfigFormat::Json => Ok(serde_json::from_str(contents)?),
+        }
+    }
+
+    fn log_filter(&self, contents: &str) -> Result<Option<LogFilter>, ConfigError> {
+        let filter = match self.format {
+            ConfigFormat::Toml => toml::from_str::<toml::Table>(contents)?
+                .get(LOG_FILTER_KEY)
+                .map(|value| {
+                    value
+                        .as_str()
+                        .map(str::to_owned)
+                        .ok_or_else(|| value.to_string())
+                }),
+            ConfigFormat::Json => serde_json::from_str::<serde_json::Value>(contents)?
+                .get(LOG_FILTER_KEY)
+                .map(|value| {
+                    value
+                        .as_str()
+                        .map(str::to_owned)
+                        .ok_or_else(|| value.to_string())
+                }),
+        };
+        match filter {
+            Some(Ok(filter)) => Ok(Some(filter.parse()?)),
+            Some(Err(value)) => Err(LogFilterParseError::InvalidDirective(value).into()),
+            None => Ok(None),
+        }
+    }
+
+    fn serialize(&self, config: &C) -> Result<String, ConfigError> {
+        match self.format {
+            ConfigFormat::Toml => Ok(toml::to_string_pretty(config)?),
+            ConfigFormat::Json => Ok(serde_json::to_string_pretty(config)?),
+        }
+    }
+}
+
+impl<C> fmt::Debug for ConfigFile<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConfigFile")
+            .field("name", &self.name)
+            .field("format", &self.format)
+            .finish()
+    }
+}
+
+/// An error returned when a configuration file could not be loaded.
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    #[error("invalid TOML: {0}")]
+    TomlDeserialize(#[from] toml::de::Error),
+    #[error("failed to serialize TOML: {0}")]
+    TomlSerialize(#[from] toml::ser::Error),
+    #[error("invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("invalid log filter: {0}")]
+    LogFilter(#[from] LogFilterParseError),
+}
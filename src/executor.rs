@@ -0,0 +1,286 @@
+//! A single-threaded executor for futures driven by the game loop.
+//!
+//! Futures spawned with [`SdkEnv::spawn_local`] are polled on the game thread during the update
+//! callback of the [`Running`](StateType::Running) state. This allows writing logic spanning
+//! multiple frames as `async` code, using the timer futures provided by this module or any other
+//! future, like [`MainThreadTask`](crate::MainThreadTask).
+//!
+//! # Example
+//! ```rust
+//! use std::time::Duration;
+//!
+//! use red4ext_rs::{SdkEnv, executor};
+//!
+//! fn spawn_example(env: &SdkEnv) {
+//!     env.spawn_local(async {
+//!         executor::sleep(Duration::from_secs(2)).await;
+//!         // do something two seconds later...
+//!         executor::next_frame().await;
+//!         // and then on the next frame...
+//!     });
+//! }
+//! ```
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Wake, Waker};
+use std::time::Duration;
+use std::{fmt, mem};
+
+use crate::types::EngineTime;
+use crate::unwind::catch_unwind;
+use crate::{
+    ClosureListener, ENV, MainThread, SdkEnv, StateHandlerResult, StateType, TaskHandle, lock,
+};
+
+thread_local! {
+    static EXECUTOR: LocalExecutor = LocalExecutor::default();
+}
+
+/// Spawns a future on the game thread executor.
+/// Returns a handle that can be used to cancel the future.
+///
+/// # Panics
+/// Panics if called from a thread other than the game thread.
+pub(crate) fn spawn_local<F>(env: &SdkEnv, future: F) -> TaskHandle
+where
+    F: Future<Output = ()> + 'static,
+{
+    assert!(
+        MainThread::is_current(),
+        "spawn_local should be called on the game thread"
+    );
+    // the scheduler provides the clock used by timers
+    env.scheduler();
+
+    EXECUTOR.with(|executor| {
+        if !executor.attached.replace(true) {
+            let listener = ClosureListener::default().with_on_update(|_| {
+                let now = current_time();
+                EXECUTOR.with(|executor| executor.tick(now));
+                StateHandlerResult::Finished
+            });
+            env.add_closure_listener(StateType::Running, listener);
+        }
+        executor.spawn(Box::pin(future))
+    })
+}
+
+/// Returns a future that completes after the specified [`Duration`] has passed.
+/// The time is measured using the clock of the [`Scheduler`](crate::Scheduler), which follows
+/// the simulation time of the game and doesn't advance while the game is paused.
+#[inline]
+pub fn sleep(duration: Duration) -> Sleep {
+    Sleep::new(current_time() + duration.as_secs_f64())
+}
+
+/// Returns a future that completes once the clock of the [`Scheduler`](crate::Scheduler)
+/// reaches the specified time.
+#[inline]
+pub fn sleep_until(deadline: EngineTime) -> Sleep {
+    Sleep::new(deadline.as_secs_f64())
+}
+
+/// Returns a future that completes on the next frame.
+#[inline]
+pub fn next_frame() -> NextFrame {
+    NextFrame { yielded: false }
+}
+
+/// A future returned by [`sleep`] and [`sleep_until`].
+/// It can only be used within futures spawned with [`SdkEnv::spawn_local`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless awaited"]
+pub struct Sleep {
+    deadline: f64,
+    timer: Option<u64>,
+}
+
+impl Sleep {
+    #[inline]
+    fn new(deadline: f64) -> Self {
+        Self {
+            deadline,
+            timer: None,
+        }
+    }
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if current_time() >= self.deadline {
+            return Poll::Ready(());
+        }
+        // the timer is registered on the first poll, later polls only refresh its waker
+        let deadline = self.deadline;
+        let timer = self.timer;
+        self.timer =
+            Some(EXECUTOR.with(|executor| executor.register_timer(timer, deadline, cx.waker())));
+        Poll::Pending
+    }
+}
+
+impl Drop for Sleep {
+    fn drop(&mut self) {
+        if let Some(id) = self.timer {
+            // the executor might already be gone if the future is dropped during thread exit
+            let _ = EXECUTOR.try_with(|executor| executor.timers.borrow_mut().remove(&id));
+        }
+    }
+}
+
+/// A future returned by [`next_frame`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless awaited"]
+pub struct NextFrame {
+    yielded: bool,
+}
+
+impl Future for NextFrame {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if self.yielded {
+            return Poll::Ready(());
+        }
+        self.yielded = true;
+        // tasks woken during a tick are polled on the next one
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+#[derive(Default)]
+struct LocalExecutor {
+    tasks: RefCell<HashMap<u64, LocalTask>>,
+    next_id: Cell<u64>,
+    ready: Arc<ReadyQueue>,
+    timers: RefCell<HashMap<u64, Timer>>,
+    next_timer_id: Cell<u64>,
+    attached: Cell<bool>,
+}
+
+impl LocalExecutor {
+    fn spawn(&self, future: Pin<Box<dyn Future<Output = ()>>>) -> TaskHandle {
+        let id = self.next_id.get();
+        self.next_id.set(id + 1);
+
+        let handle = TaskHandle::default();
+        let task = LocalTask {
+            future,
+            handle: handle.clone(),
+        };
+        self.tasks.borrow_mut().insert(id, task);
+        lock(&self.ready.0).push(id);
+        handle
+    }
+
+    /// Registers a timer, or updates the waker of the timer with the given id if it's still
+    /// pending. Returns the id of the timer.
+    fn register_timer(&self, id: Option<u64>, deadline: f64, waker: &Waker) -> u64 {
+        let mut timers = self.timers.borrow_mut();
+        if let Some(id) = id
+            && let Some(timer) = timers.get_mut(&id)
+        {
+            if !timer.waker.will_wake(waker) {
+                timer.waker = waker.clone();
+            }
+            return id;
+        }
+        let id = self.next_timer_id.get();
+        self.next_timer_id.set(id + 1);
+        let timer = Timer {
+            deadline,
+            waker: waker.clone(),
+        };
+        timers.insert(id, timer);
+        id
+    }
+
+    fn tick(&self, now: f64) {
+        let mut expired = vec![];
+        self.timers.borrow_mut().retain(|_, timer| {
+            if timer.deadline > now {
+                return true;
+            }
+            expired.push(timer.waker.clone());
+            false
+        });
+        expired.into_iter().for_each(Waker::wake);
+
+        self.tasks
+            .borrow_mut()
+            .retain(|_, task| !task.handle.is_cancelled());
+
+        let mut ready = mem::take(&mut *lock(&self.ready.0));
+        ready.sort_unstable();
+        ready.dedup();
+
+        for id in ready {
+            // the task is taken out while it's being polled, this allows it to spawn new tasks
+            let Some(mut task) = self.tasks.borrow_mut().remove(&id) else {
+                continue;
+            };
+            let waker = Waker::from(Arc::new(TaskWaker {
+                id,
+                queue: self.ready.clone(),
+            }));
+            let mut cx = Context::from_waker(&waker);
+            // futures that panic are dropped
+            match catch_unwind("local task", || task.future.as_mut().poll(&mut cx)) {
+                Some(Poll::Pending) => {
+                    self.tasks.borrow_mut().insert(id, task);
+                }
+                Some(Poll::Ready(())) | None => task.handle.finish(),
+            }
+        }
+    }
+}
+
+impl fmt::Debug for LocalExecutor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LocalExecutor")
+            .field("tasks", &self.tasks.borrow().len())
+            .field("timers", &self.timers.borrow().len())
+            .finish()
+    }
+}
+
+struct LocalTask {
+    future: Pin<Box<dyn Future<Output = ()>>>,
+    handle: TaskHandle,
+}
+
+struct Timer {
+    deadline: f64,
+    waker: Waker,
+}
+
+#[derive(Debug, Default)]
+struct ReadyQueue(Mutex<Vec<u64>>);
+
+struct TaskWaker {
+    id: u64,
+    queue: Arc<ReadyQueue>,
+}
+
+impl Wake for TaskWaker {
+    #[inline]
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    #[inline]
+    fn wake_by_ref(self: &Arc<Self>) {
+        lock(&self.queue.0).push(self.id);
+    }
+}
+
+#[inline]
+fn current_time() -> f64 {
+    ENV.get()
+        .map_or(0., |env| env.scheduler().now().as_secs_f64())
+}
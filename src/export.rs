@@ -1,14 +1,158 @@
-use std::ffi::CString;
+use std::ffi::{CStr, CString};
 use std::marker::PhantomData;
+use std::sync::{Mutex, Once};
+use std::{iter, mem, ptr};
 
 use sealed::sealed;
+use thiserror::Error;
 
-use crate::invocable::{GlobalMetadata, MethodMetadata};
+use crate::invocable::{GlobalMetadata, MethodMetadata, StructMethodMetadata};
 use crate::systems::RttiSystemMut;
-use crate::types::{CName, NativeClass};
-use crate::{NativeRepr, RttiSystem, ScriptClass, class_kind};
+use crate::types::{
+    Bitfield, CName, Class, Enum, GlobalFunction, IScriptable, NativeClass, PoolRef, Property,
+};
+use crate::unwind::catch_unwind;
+use crate::{
+    ClosureListener, ENV, NativeBitfield, NativeEnum, NativeRepr, RttiSystem, ScriptClass,
+    StateHandlerResult, StateType, class_kind, lock,
+};
+
+/// Everything registered by the exports, so that it can be removed from RTTI when the plugin is
+/// unloaded.
+static REGISTERED: Mutex<Vec<Registration>> = Mutex::new(Vec::new());
+
+#[derive(Debug, Clone, Copy)]
+enum Registration {
+    Type(CName),
+    Function(usize),
+    Method(CName, CName),
+    StaticMethod(CName, CName),
+}
+
+#[inline]
+fn track(registration: Registration) {
+    lock(&REGISTERED).push(registration);
+}
+
+/// Unregisters all types and functions registered by the exports, in reverse order of
+/// registration. Methods added to existing classes are removed from them. This prevents scripts
+/// from calling into the plugin after it's unloaded.
+pub(crate) fn unregister_all() {
+    let registered = mem::take(&mut *lock(&REGISTERED));
+    if registered.is_empty() {
+        return;
+    }
+    let mut rtti = RttiSystemMut::get();
+    for registration in registered.into_iter().rev() {
+        match registration {
+            Registration::Type(name) => {
+                rtti.unregister_type(name);
+            }
+            Registration::Function(ptr) => unsafe {
+                rtti.unregister_function(ptr as *const GlobalFunction);
+            },
+            Registration::Method(class, name) => {
+                if let Some(class) = rtti.get_class(class) {
+                    class.remove_method(name);
+                }
+            }
+            Registration::StaticMethod(class, name) => {
+                if let Some(class) = rtti.get_class(class) {
+                    class.remove_static_method(name);
+                }
+            }
+        }
+    }
+}
+
+/// Classes with a scripted base that did not exist when they were registered, along with the
+/// name of the base. The base is resolved once scripts are loaded.
+static DEFERRED_BASES: Mutex<Vec<(&'static str, &'static str)>> = Mutex::new(Vec::new());
+static DEFERRED_BASES_LISTENER: Once = Once::new();
+
+fn defer_base(class: &'static str, base: &'static str) {
+    lock(&DEFERRED_BASES).push((class, base));
+    let Some(env) = ENV.get() else {
+        return;
+    };
+    DEFERRED_BASES_LISTENER.call_once(|| {
+        // scripts are loaded during the base initialization, so they should be available by the
+        // time the game enters the initialization state, bases still missing then are retried
+        // once the game is running
+        let listener = ClosureListener::default().with_on_enter(|_| {
+            resolve_deferred_bases(false);
+            StateHandlerResult::Finished
+        });
+        env.add_closure_listener(StateType::Initialization, listener);
+        let listener = ClosureListener::default().with_on_enter(|_| {
+            resolve_deferred_bases(true);
+            StateHandlerResult::Finished
+        });
+        env.add_closure_listener(StateType::Running, listener);
+    });
+}
+
+/// Resolves the deferred bases. Bases that don't exist yet are kept for later, unless this is
+/// the last attempt, in which case every failure is reported.
+fn resolve_deferred_bases(last_attempt: bool) {
+    let deferred = mem::take(&mut *lock(&DEFERRED_BASES));
+    let mut pending = vec![];
+    for (class, base) in deferred {
+        match resolve_base(class, base) {
+            Ok(()) => {}
+            Err(BaseError::Missing) if !last_attempt => pending.push((class, base)),
+            Err(err) => {
+                if let Some(env) = ENV.get() {
+                    env.error(format_args!(
+                        "failed to resolve the base {base} of {class}, it will keep extending \
+                         IScriptable: {err}"
+                    ));
+                }
+            }
+        }
+    }
+    lock(&DEFERRED_BASES).extend(pending);
+}
+
+#[derive(Debug, Error)]
+enum BaseError {
+    #[error("the base class does not exist")]
+    Missing,
+    #[error("the class is not registered")]
+    NotRegistered,
+    #[error("the class is smaller than the native type its base is built on")]
+    TooSmall,
+}
+
+fn resolve_base(class_name: &'static str, base_name: &'static str) -> Result<(), BaseError> {
+    let (base, native_size) = {
+        let rtti = RttiSystem::get();
+        let base = rtti
+            .get_class(CName::new(base_name))
+            .or_else(|| rtti.get_class_by_script_name(CName::new(base_name)))
+            .ok_or(BaseError::Missing)?;
+        // the Rust type has to embed the native type the scripted base is built on
+        let native_size = iter::once(base)
+            .chain(base.base_iter())
+            .find(|class| class.flags().is_native())
+            .map_or(0, Class::size);
+        (base as *const Class, native_size)
+    };
+
+    let mut rtti = RttiSystemMut::get();
+    let class = rtti
+        .get_class(CName::new(class_name))
+        .ok_or(BaseError::NotRegistered)?;
+    if class.size() < native_size {
+        return Err(BaseError::TooSmall);
+    }
+    class.set_base(unsafe { &*base });
+    Ok(())
+}
 
 /// A list of exports to register with the game.
+/// Classes and structs are registered after the exported types they extend, regardless of their
+/// position in the list.
 #[derive(Debug)]
 pub struct ExportList<H, T> {
     head: H,
@@ -27,6 +171,51 @@ impl<H, T> ExportList<H, T> {
 pub trait Exportable {
     fn register(&self);
     fn post_register(&self);
+
+    /// Appends the individual exports contained in this export to `out`.
+    #[doc(hidden)]
+    #[inline]
+    fn flatten<'a>(&'a self, out: &mut Vec<&'a dyn Exportable>)
+    where
+        Self: Sized,
+    {
+        out.push(self);
+    }
+
+    /// Returns the name of the type defined by this export, if any.
+    #[doc(hidden)]
+    #[inline]
+    fn provides(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Returns the name of the type this export depends on, if any.
+    #[doc(hidden)]
+    #[inline]
+    fn requires(&self) -> Option<&'static str> {
+        None
+    }
+}
+
+impl<H, T> ExportList<H, T>
+where
+    H: Exportable,
+    T: Exportable,
+{
+    /// Returns the exports in this list in the order they should be registered in, so that
+    /// types are registered after the types they depend on.
+    fn ordered(&self) -> Vec<&dyn Exportable> {
+        let mut exports = vec![];
+        self.flatten(&mut exports);
+        let nodes = exports
+            .iter()
+            .map(|export| (export.provides(), export.requires()))
+            .collect::<Vec<_>>();
+        registration_order(&nodes)
+            .into_iter()
+            .map(|i| exports[i])
+            .collect()
+    }
 }
 
 #[sealed]
@@ -35,16 +224,27 @@ where
     H: Exportable,
     T: Exportable,
 {
-    #[inline]
     fn register(&self) {
-        self.head.register();
-        self.tail.register();
+        // a panicking export does not prevent the others from being registered
+        for export in self.ordered() {
+            let name = export.provides().unwrap_or("export");
+            catch_unwind(format_args!("registration of {name}"), || export.register());
+        }
     }
 
-    #[inline]
     fn post_register(&self) {
-        self.head.post_register();
-        self.tail.post_register();
+        for export in self.ordered() {
+            let name = export.provides().unwrap_or("export");
+            catch_unwind(format_args!("post-registration of {name}"), || {
+                export.post_register();
+            });
+        }
+    }
+
+    #[inline]
+    fn flatten<'a>(&'a self, out: &mut Vec<&'a dyn Exportable>) {
+        self.head.flatten(out);
+        self.tail.flatten(out);
     }
 }
 
@@ -59,24 +259,79 @@ impl Exportable for ExportNil {
 
     #[inline]
     fn post_register(&self) {}
+
+    #[inline]
+    fn flatten<'a>(&'a self, _out: &mut Vec<&'a dyn Exportable>) {}
+}
+
+/// Computes the order in which exports should be registered. Each node is a pair of the type
+/// an export provides and the type it requires. Exports are moved after the exports providing
+/// the types they require and otherwise keep their relative order. Dependency cycles are left
+/// unresolved, in which case the affected exports keep their original order.
+fn registration_order(nodes: &[(Option<&str>, Option<&str>)]) -> Vec<usize> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum State {
+        Pending,
+        Visiting,
+        Done,
+    }
+
+    fn visit(
+        i: usize,
+        nodes: &[(Option<&str>, Option<&str>)],
+        states: &mut [State],
+        order: &mut Vec<usize>,
+    ) {
+        if states[i] != State::Pending {
+            return;
+        }
+        states[i] = State::Visiting;
+        if let Some(required) = nodes[i].1 {
+            let dependency = nodes
+                .iter()
+                .position(|&(provided, _)| provided == Some(required));
+            if let Some(j) = dependency.filter(|&j| j != i) {
+                visit(j, nodes, states, order);
+            }
+        }
+        states[i] = State::Done;
+        order.push(i);
+    }
+
+    let mut states = vec![State::Pending; nodes.len()];
+    let mut order = Vec::with_capacity(nodes.len());
+    for i in 0..nodes.len() {
+        visit(i, nodes, &mut states, &mut order);
+    }
+    order
 }
 
 /// A single class export.
 /// This can be used to define a custom class to be exported to the game.
 /// This type should not be used for structs, use [`StructExport`] instead.
+///
+/// The base class can be a class defined in scripts, see
+/// [`ClassExportBuilder::scripted_base`]. The class temporarily extends `IScriptable` and the
+/// base is resolved once scripts are loaded. In that case the Rust type must embed the native
+/// type the base is built on, and the properties of the base are stored in the value holder of
+/// the instance, like for any scripted class.
 #[derive(Debug)]
 pub struct ClassExport<C: 'static> {
     base: &'static str,
+    scripted_base: bool,
     methods: &'static [MethodMetadata<C>],
     static_methods: &'static [GlobalMetadata],
+    fields: &'static [FieldMetadata<C>],
 }
 
 impl<C: ScriptClass> ClassExport<C> {
     pub fn builder() -> ClassExportBuilder<C> {
         ClassExportBuilder {
             base: "IScriptable",
+            scripted_base: false,
             methods: &[],
             static_methods: &[],
+            fields: &[],
         }
     }
 }
@@ -86,19 +341,47 @@ impl<C: Default + Clone + ScriptClass<Kind = class_kind::Native>> Exportable for
     fn register(&self) {
         let mut rtti = RttiSystemMut::get();
         let name_cstr = CString::new(C::NAME).expect("name should be valid");
-        let base = rtti
-            .get_class(CName::new(self.base))
-            .expect("base should exist");
-        let handle = NativeClass::<C>::new_handle(&name_cstr, Some(base));
+        let handle = match rtti.get_class(CName::new(self.base)) {
+            Some(base) => NativeClass::<C>::new_handle(&name_cstr, Some(base)),
+            None if self.scripted_base => {
+                defer_base(C::NAME, self.base);
+                let base = rtti
+                    .get_class(CName::new(IScriptable::NAME))
+                    .expect("IScriptable should exist");
+                NativeClass::<C>::new_handle(&name_cstr, Some(base))
+            }
+            None => {
+                if let Some(env) = ENV.get() {
+                    env.error(format_args!(
+                        "skipping class {}, its base {} does not exist",
+                        C::NAME,
+                        self.base
+                    ));
+                }
+                return;
+            }
+        };
         rtti.register_class(handle);
+        track(Registration::Type(CName::new(C::NAME)));
+    }
+
+    #[inline]
+    fn provides(&self) -> Option<&'static str> {
+        Some(C::NAME)
+    }
+
+    #[inline]
+    fn requires(&self) -> Option<&'static str> {
+        Some(self.base)
     }
 
     fn post_register(&self) {
-        let (converted_methods, converted_static_methods) = {
+        let (converted_methods, converted_static_methods, converted_fields) = {
             let rtti_ro = RttiSystem::get();
-            let class = rtti_ro
-                .get_class(CName::new(C::NAME))
-                .expect("class should exist");
+            // the class is not registered if its base could not be found
+            let Some(class) = rtti_ro.get_class(CName::new(C::NAME)) else {
+                return;
+            };
             let converted_methods = self
                 .methods
                 .iter()
@@ -109,7 +392,12 @@ impl<C: Default + Clone + ScriptClass<Kind = class_kind::Native>> Exportable for
                 .iter()
                 .map(|m| m.to_rtti_static_method(class))
                 .collect::<Vec<_>>();
-            (converted_methods, converted_static_methods)
+            let converted_fields = convert_fields(self.fields, &rtti_ro, class);
+            (
+                converted_methods,
+                converted_static_methods,
+                converted_fields,
+            )
         };
 
         let mut rtti_rw = RttiSystemMut::get();
@@ -123,6 +411,9 @@ impl<C: Default + Clone + ScriptClass<Kind = class_kind::Native>> Exportable for
         for static_method in converted_static_methods {
             class.add_static_method(static_method);
         }
+        for field in converted_fields {
+            class.add_property(field);
+        }
     }
 }
 
@@ -130,15 +421,37 @@ impl<C: Default + Clone + ScriptClass<Kind = class_kind::Native>> Exportable for
 #[derive(Debug)]
 pub struct ClassExportBuilder<C: 'static> {
     base: &'static str,
+    scripted_base: bool,
     methods: &'static [MethodMetadata<C>],
     static_methods: &'static [GlobalMetadata],
+    fields: &'static [FieldMetadata<C>],
 }
 
 impl<C> ClassExportBuilder<C> {
     /// Set the base class of the class to be exported.
-    /// This is set to `IScriptable` by default.
+    /// This is set to `IScriptable` by default. The base must be a native class, if it does not
+    /// exist when the class is registered, an error is logged and the class is not registered.
     pub const fn base(mut self, base: &'static str) -> Self {
         self.base = base;
+        self.scripted_base = false;
+        self
+    }
+
+    /// Set the base class of the class to be exported to a class defined in scripts. The base is
+    /// resolved once scripts are loaded, and an error is logged if it does not exist by the time
+    /// the game is running.
+    pub const fn scripted_base(mut self, base: &'static str) -> Self {
+        self.base = base;
+        self.scripted_base = true;
+        self
+    }
+
+    /// Set the base class of the class to be exported to a class with a Rust definition. Like
+    /// with [`base`](Self::base), the base must exist when the class is registered, a base
+    /// defined in scripts has to be set with [`scripted_base`](Self::scripted_base) instead.
+    pub const fn base_class<B: ScriptClass>(mut self) -> Self {
+        self.base = B::NAME;
+        self.scripted_base = false;
         self
     }
 
@@ -156,31 +469,79 @@ impl<C> ClassExportBuilder<C> {
         self
     }
 
+    /// Set the fields of the class to be exported as properties.
+    /// See the [`fields!`](crate::fields) macro for a convenient way to define fields.
+    pub const fn fields(mut self, fields: &'static [FieldMetadata<C>]) -> Self {
+        self.fields = fields;
+        self
+    }
+
     /// Build the final [`ClassExport`] instance.
     pub const fn build(self) -> ClassExport<C> {
         ClassExport {
             base: self.base,
+            scripted_base: self.scripted_base,
             methods: self.methods,
             static_methods: self.static_methods,
+            fields: self.fields,
         }
     }
 }
 
 /// A single struct export.
 /// This can be used to define a custom struct to be exported to the game.
+///
+/// # Example
+/// ```rust
+/// use red4ext_rs::{NativeRepr, StructExport, fields, struct_methods};
+///
+/// #[derive(Debug, Default, Clone)]
+/// #[repr(C)]
+/// struct Point {
+///     x: f32,
+///     y: f32,
+/// }
+///
+/// unsafe impl NativeRepr for Point {
+///     const NAME: &'static str = "Point";
+/// }
+///
+/// impl Point {
+///     fn length(&self) -> f32 {
+///         self.x.hypot(self.y)
+///     }
+///
+///     fn scale(&mut self, factor: f32) {
+///         self.x *= factor;
+///         self.y *= factor;
+///     }
+/// }
+///
+/// fn export() -> StructExport<Point> {
+///     StructExport::<Point>::builder()
+///         .fields(fields![c"x" => Point::x, c"y" => Point::y])
+///         .methods(struct_methods![
+///             c"Length" => Point::length,
+///             c"Scale" => Point::scale,
+///         ])
+///         .build()
+/// }
+/// ```
 #[derive(Debug)]
-pub struct StructExport<C> {
+pub struct StructExport<C: 'static> {
     base: Option<&'static str>,
+    methods: &'static [StructMethodMetadata<C>],
     static_methods: &'static [GlobalMetadata],
-    _phantom: PhantomData<fn() -> C>,
+    fields: &'static [FieldMetadata<C>],
 }
 
 impl<C> StructExport<C> {
     pub fn builder() -> StructExportBuilder<C> {
         StructExportBuilder {
             base: None,
+            methods: &[],
             static_methods: &[],
-            _phantom: PhantomData,
+            fields: &[],
         }
     }
 }
@@ -190,23 +551,55 @@ impl<C: Default + Clone + NativeRepr> Exportable for StructExport<C> {
     fn register(&self) {
         let mut rtti = RttiSystemMut::get();
         let name_cstr = CString::new(C::NAME).expect("name should be valid");
-        let base = self
-            .base
-            .map(|base| &*rtti.get_class(CName::new(base)).expect("base should exist"));
+        let base = match self.base {
+            Some(base) => match rtti.get_class(CName::new(base)) {
+                Some(base) => Some(&*base),
+                None => {
+                    if let Some(env) = ENV.get() {
+                        env.error(format_args!(
+                            "skipping struct {}, its base {base} does not exist",
+                            C::NAME
+                        ));
+                    }
+                    return;
+                }
+            },
+            None => None,
+        };
         let handle = NativeClass::<C>::new_handle(&name_cstr, base);
         rtti.register_class(handle);
+        track(Registration::Type(CName::new(C::NAME)));
+    }
+
+    #[inline]
+    fn provides(&self) -> Option<&'static str> {
+        Some(C::NAME)
+    }
+
+    #[inline]
+    fn requires(&self) -> Option<&'static str> {
+        self.base
     }
 
     fn post_register(&self) {
-        let converted_static_methods = {
+        let (converted_static_methods, converted_fields) = {
             let rtti_ro = RttiSystem::get();
-            let class = rtti_ro
-                .get_class(CName::new(C::NAME))
-                .expect("class should exist");
-            self.static_methods
+            // the struct is not registered if its base could not be found
+            let Some(class) = rtti_ro.get_class(CName::new(C::NAME)) else {
+                return;
+            };
+            let converted_static_methods = self
+                .methods
                 .iter()
-                .map(|m| m.to_rtti_static_method(class))
-                .collect::<Vec<_>>()
+                .map(|m| m.to_rtti(class))
+                .chain(
+                    self.static_methods
+                        .iter()
+                        .map(|m| m.to_rtti_static_method(class)),
+                )
+                .collect::<Vec<_>>();
+            let converted_fields = convert_fields(self.fields, &rtti_ro, class);
+            (converted_static_methods, converted_fields)
         };
 
         let mut rtti_rw = RttiSystemMut::get();
@@ -217,25 +610,39 @@ impl<C: Default + Clone + NativeRepr> Exportable for StructExport<C> {
         for static_method in converted_static_methods {
             class.add_static_method(static_method);
         }
+        for field in converted_fields {
+            class.add_property(field);
+        }
     }
 }
 
 /// A builder for [`StructExport`].
 #[derive(Debug)]
-pub struct StructExportBuilder<C> {
+pub struct StructExportBuilder<C: 'static> {
     base: Option<&'static str>,
+    methods: &'static [StructMethodMetadata<C>],
     static_methods: &'static [GlobalMetadata],
-    _phantom: PhantomData<fn() -> C>,
+    fields: &'static [FieldMetadata<C>],
 }
 
 impl<C> StructExportBuilder<C> {
     /// Set the base type of the struct to be exported.
-    /// Structs do not have a base type by default.
+    /// Structs do not have a base type by default. If the base does not exist when the struct is
+    /// registered, an error is logged and the struct is not registered.
     pub const fn base(mut self, base: &'static str) -> Self {
         self.base = Some(base);
         self
     }
 
+    /// Set the instance methods of the struct to be exported. They're registered as static
+    /// methods accepting a `script_ref` to the struct as their first argument.
+    /// See the [`struct_methods!`](crate::struct_methods) macro for a convenient way to define
+    /// methods.
+    pub const fn methods(mut self, methods: &'static [StructMethodMetadata<C>]) -> Self {
+        self.methods = methods;
+        self
+    }
+
     /// Set the static methods of the struct to be exported.
     /// See the [`static_methods!`](crate::static_methods) macro for a convenient way to define methods.
     pub const fn static_methods(mut self, static_methods: &'static [GlobalMetadata]) -> Self {
@@ -243,14 +650,408 @@ impl<C> StructExportBuilder<C> {
         self
     }
 
+    /// Set the fields of the struct to be exported as properties. Scripts can read and write
+    /// the fields of the struct like the fields of any other native struct.
+    /// See the [`fields!`](crate::fields) macro for a convenient way to define fields.
+    pub const fn fields(mut self, fields: &'static [FieldMetadata<C>]) -> Self {
+        self.fields = fields;
+        self
+    }
+
     /// Build the final [`StructExport`] instance.
     pub const fn build(self) -> StructExport<C> {
         StructExport {
             base: self.base,
+            methods: self.methods,
+            static_methods: self.static_methods,
+            fields: self.fields,
+        }
+    }
+}
+
+/// A single enum export.
+/// This can be used to define a custom enum to be exported to the game. The enum is registered
+/// under its [`NativeEnum::NAME`] and can be used in the signatures of exported functions.
+///
+/// # Example
+/// ```rust
+/// use red4ext_rs::{EnumExport, native_enum};
+///
+/// native_enum! {
+///     #[derive(Debug, Clone, Copy, Default)]
+///     enum Mode: i32 {
+///         #[default]
+///         Idle = 0,
+///         Walking = 1,
+///         Running = 2,
+///     }
+/// }
+///
+/// const EXPORT: EnumExport<Mode> = EnumExport::new();
+/// ```
+#[derive(Debug)]
+pub struct EnumExport<E>(PhantomData<fn() -> E>);
+
+impl<E> EnumExport<E> {
+    #[inline]
+    pub const fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<E> Default for EnumExport<E> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[sealed]
+impl<E: NativeEnum> Exportable for EnumExport<E> {
+    fn register(&self) {
+        let size = match mem::size_of::<E::Raw>() {
+            size @ (1 | 2 | 4 | 8) => size as u8,
+            size => {
+                if let Some(env) = ENV.get() {
+                    env.error(format_args!(
+                        "skipping enum {}, its size {size} is not supported",
+                        E::NAME
+                    ));
+                }
+                return;
+            }
+        };
+        let name_cstr = CString::new(E::NAME).expect("name should be valid");
+        let handle = Enum::new_handle(&name_cstr, size, E::VARIANTS);
+        RttiSystemMut::get().register_enum(handle);
+        track(Registration::Type(CName::new(E::NAME)));
+    }
+
+    #[inline]
+    fn post_register(&self) {}
+}
+
+/// A single bitfield export.
+/// This can be used to define a custom bitfield to be exported to the game. The bitfield is
+/// registered under its [`NativeRepr::NAME`] and can be used in the signatures of exported
+/// functions.
+///
+/// # Example
+/// ```rust
+/// use red4ext_rs::{BitfieldExport, native_bitfield};
+///
+/// native_bitfield! {
+///     #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+///     struct Abilities: u32 {
+///         const JUMP = 0;
+///         const DASH = 1;
+///         const CLIMB = 2;
+///     }
+/// }
+///
+/// const EXPORT: BitfieldExport<Abilities> = BitfieldExport::new();
+///
+/// fn can_escape(abilities: Abilities) -> bool {
+///     abilities.contains(Abilities::JUMP | Abilities::CLIMB)
+/// }
+/// ```
+#[derive(Debug)]
+pub struct BitfieldExport<B>(PhantomData<fn() -> B>);
+
+impl<B> BitfieldExport<B> {
+    #[inline]
+    pub const fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<B> Default for BitfieldExport<B> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[sealed]
+impl<B: NativeBitfield> Exportable for BitfieldExport<B> {
+    fn register(&self) {
+        let size = match mem::size_of::<B>() {
+            size @ (1 | 2 | 4 | 8) => size as u8,
+            size => {
+                if let Some(env) = ENV.get() {
+                    env.error(format_args!(
+                        "skipping bitfield {}, its size {size} is not supported",
+                        B::NAME
+                    ));
+                }
+                return;
+            }
+        };
+        if let Some(env) = ENV.get() {
+            for &(bit_name, bit) in B::BITS {
+                if !Bitfield::is_valid_bit(size, bit) || bit_name.contains('\0') {
+                    env.error(format_args!(
+                        "skipping bit {bit_name} of bitfield {}, it's invalid or out of range",
+                        B::NAME
+                    ));
+                }
+            }
+        }
+        let name_cstr = CString::new(B::NAME).expect("name should be valid");
+        let handle = Bitfield::new_handle(&name_cstr, size, B::BITS);
+        RttiSystemMut::get().register_bitfield(handle);
+        track(Registration::Type(CName::new(B::NAME)));
+    }
+
+    #[inline]
+    fn post_register(&self) {}
+}
+
+/// An extension of an existing class, such as a class defined by the game.
+/// This can be used to add native methods to a class that is not created by the plugin. The
+/// methods are added to the class once all types are registered, and removed from it when
+/// the plugin is unloaded.
+///
+/// The methods receive the instance as `&C`, so the layout of `C` must match the native class
+/// named [`ScriptClass::NAME`]. The extension is skipped if the class does not exist, if its
+/// name refers to another class in scripts, if it's not native or smaller than `C`, or if it
+/// already defines a method with the same name.
+///
+/// # Example
+/// ```rust
+/// use red4ext_rs::types::IScriptable;
+/// use red4ext_rs::{ClassExtension, ScriptClass, class_kind, methods};
+///
+/// #[derive(Debug, Default, Clone)]
+/// #[repr(C)]
+/// struct GameObject {
+///     base: IScriptable,
+/// }
+///
+/// impl GameObject {
+///     fn is_extended(&self) -> bool {
+///         true
+///     }
+/// }
+///
+/// unsafe impl ScriptClass for GameObject {
+///     const NAME: &'static str = "gameObject";
+///     type Kind = class_kind::Native;
+/// }
+///
+/// fn export() -> ClassExtension<GameObject> {
+///     ClassExtension::<GameObject>::builder()
+///         .methods(methods![c"IsExtended" => GameObject::is_extended])
+///         .build()
+/// }
+/// ```
+#[derive(Debug)]
+pub struct ClassExtension<C: 'static> {
+    methods: &'static [MethodMetadata<C>],
+    static_methods: &'static [GlobalMetadata],
+}
+
+impl<C: ScriptClass> ClassExtension<C> {
+    pub fn builder() -> ClassExtensionBuilder<C> {
+        ClassExtensionBuilder {
+            methods: &[],
+            static_methods: &[],
+        }
+    }
+
+    fn check_class(rtti: &RttiSystem, class: &Class) -> Result<(), String> {
+        // the class is looked up by its native name, which must not refer to another class in
+        // scripts
+        if rtti
+            .get_class_by_script_name(CName::new(C::NAME))
+            .is_some_and(|scripted| !ptr::eq(scripted, class))
+        {
+            return Err(format!(
+                "class {} does not match its Rust type {}",
+                C::NAME,
+                std::any::type_name::<C>()
+            ));
+        }
+        if !class.flags().is_native() {
+            return Err(format!("class {} is not native", C::NAME));
+        }
+        if (class.size() as usize) < mem::size_of::<C>() {
+            return Err(format!(
+                "class {} is smaller than its Rust type {}",
+                C::NAME,
+                std::any::type_name::<C>()
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[sealed]
+impl<C: ScriptClass<Kind = class_kind::Native>> Exportable for ClassExtension<C> {
+    #[inline]
+    fn register(&self) {}
+
+    fn post_register(&self) {
+        let report = |err: &str| {
+            if let Some(env) = ENV.get() {
+                env.error(format_args!("failed to extend {}: {err}", C::NAME));
+            }
+        };
+
+        let (converted_methods, converted_static_methods) = {
+            let rtti_ro = RttiSystem::get();
+            let Some(class) = rtti_ro.get_class(CName::new(C::NAME)) else {
+                report("class does not exist");
+                return;
+            };
+            if let Err(err) = Self::check_class(&rtti_ro, class) {
+                report(&err);
+                return;
+            }
+            let converted_methods = self
+                .methods
+                .iter()
+                .map(|m| m.to_rtti(class))
+                .collect::<Vec<_>>();
+            let converted_static_methods = self
+                .static_methods
+                .iter()
+                .map(|m| m.to_rtti_static_method(class))
+                .collect::<Vec<_>>();
+            (converted_methods, converted_static_methods)
+        };
+
+        let mut rtti_rw = RttiSystemMut::get();
+        let class = rtti_rw
+            .get_class(CName::new(C::NAME))
+            .expect("class should exist");
+
+        for method in converted_methods {
+            let name = method.as_function().name();
+            if class
+                .methods()
+                .iter()
+                .any(|m| m.as_function().name() == name)
+            {
+                report(&format!("method {name} is already defined"));
+                continue;
+            }
+            class.add_method(method);
+            track(Registration::Method(CName::new(C::NAME), name));
+        }
+        for static_method in converted_static_methods {
+            let name = static_method.as_function().name();
+            if class
+                .static_methods()
+                .iter()
+                .any(|m| m.as_function().name() == name)
+            {
+                report(&format!("static method {name} is already defined"));
+                continue;
+            }
+            class.add_static_method(static_method);
+            track(Registration::StaticMethod(CName::new(C::NAME), name));
+        }
+    }
+
+    #[inline]
+    fn requires(&self) -> Option<&'static str> {
+        Some(C::NAME)
+    }
+}
+
+/// A builder for [`ClassExtension`].
+#[derive(Debug)]
+pub struct ClassExtensionBuilder<C: 'static> {
+    methods: &'static [MethodMetadata<C>],
+    static_methods: &'static [GlobalMetadata],
+}
+
+impl<C> ClassExtensionBuilder<C> {
+    /// Set the methods to be added to the class.
+    /// See the [`methods!`](crate::methods) macro for a convenient way to define methods.
+    pub const fn methods(mut self, methods: &'static [MethodMetadata<C>]) -> Self {
+        self.methods = methods;
+        self
+    }
+
+    /// Set the static methods to be added to the class.
+    /// See the [`static_methods!`](crate::static_methods) macro for a convenient way to define methods.
+    pub const fn static_methods(mut self, static_methods: &'static [GlobalMetadata]) -> Self {
+        self.static_methods = static_methods;
+        self
+    }
+
+    /// Build the final [`ClassExtension`] instance.
+    pub const fn build(self) -> ClassExtension<C> {
+        ClassExtension {
+            methods: self.methods,
             static_methods: self.static_methods,
+        }
+    }
+}
+
+/// A field of an exported type, registered as an RTTI property.
+/// See the [`fields!`](crate::fields) macro for a convenient way to define fields.
+#[derive(Debug)]
+pub struct FieldMetadata<C> {
+    name: &'static CStr,
+    offset: usize,
+    typ: &'static str,
+    _phantom: PhantomData<fn() -> C>,
+}
+
+impl<C> FieldMetadata<C> {
+    /// Creates a field with the given name and offset. The type of the field is inferred from
+    /// the accessor function, which is otherwise unused.
+    #[doc(hidden)]
+    #[inline]
+    pub const fn new<F: NativeRepr>(
+        name: &'static CStr,
+        offset: usize,
+        _accessor: fn(&C) -> &F,
+    ) -> Self {
+        Self {
+            name,
+            offset,
+            typ: F::NAME,
             _phantom: PhantomData,
         }
     }
+
+    /// Converts this metadata into a [`Property`] instance, which can be added to the class.
+    /// Returns [`None`] if the type of the field is not registered.
+    pub fn to_rtti(&self, rtti: &RttiSystem, class: &Class) -> Option<PoolRef<Property>> {
+        let typ = rtti.get_type(CName::new(self.typ))?;
+        let offset = u32::try_from(self.offset).ok()?;
+        Some(Property::new(self.name, c"", typ, class, offset))
+    }
+}
+
+/// Converts the fields of an exported type into properties. Fields that can't be converted are
+/// skipped, so that a single field doesn't prevent the rest of the type from being registered.
+fn convert_fields<C>(
+    fields: &[FieldMetadata<C>],
+    rtti: &RttiSystem,
+    class: &Class,
+) -> Vec<PoolRef<Property>> {
+    fields
+        .iter()
+        .filter_map(|field| {
+            let converted = field.to_rtti(rtti, class);
+            if converted.is_none()
+                && let Some(env) = ENV.get()
+            {
+                env.error(format_args!(
+                    "skipping field {} of {}, its type {} does not exist",
+                    field.name.to_string_lossy(),
+                    class.name().as_str(),
+                    field.typ
+                ));
+            }
+            converted
+        })
+        .collect()
 }
 
 /// A single global function export.
@@ -264,9 +1065,11 @@ impl Exportable for GlobalExport {
 
     fn post_register(&self) {
         let converted = self.0.to_rtti();
+        let ptr: *const GlobalFunction = &*converted;
 
         let mut rtti = RttiSystemMut::get();
         rtti.register_function(converted);
+        track(Registration::Function(ptr as usize));
     }
 }
 
@@ -329,6 +1132,234 @@ macro_rules! exports {
     [] => { $crate::ExportNil }
 }
 
+/// Define a list of fields to register with the game as properties. The fields are specified as
+/// `Type::field` and their types must implement [`NativeRepr`]. Usually used in conjuction with
+/// [`exports!`].
+///
+/// # Example
+/// ```rust
+/// use red4ext_rs::{ClassExport, ScriptClass, class_kind, fields};
+/// use red4ext_rs::types::IScriptable;
+///
+/// #[derive(Debug, Default, Clone)]
+/// #[repr(C)]
+/// struct Counter {
+///     base: IScriptable,
+///     count: i32,
+///     step: f32,
+/// }
+///
+/// unsafe impl ScriptClass for Counter {
+///     const NAME: &'static str = "Counter";
+///     type Kind = class_kind::Native;
+/// }
+///
+/// fn export() -> ClassExport<Counter> {
+///     ClassExport::<Counter>::builder()
+///         .fields(fields![
+///             c"count" => Counter::count,
+///             c"step" => Counter::step,
+///         ])
+///         .build()
+/// }
+/// ```
+#[macro_export]
+macro_rules! fields {
+    [$($name:literal => $ty:ident::$field:ident),*$(,)?] => {
+        const {
+            &[$($crate::FieldMetadata::new(
+                $name,
+                ::std::mem::offset_of!($ty, $field),
+                |this: &$ty| &this.$field,
+            )),*]
+        }
+    };
+}
+
+/// Defines a fieldless enum that can be exported with [`EnumExport`]. The enum is declared
+/// along with its primitive integer representation, and all of its variants must have explicit
+/// discriminants. Implements [`NativeEnum`](crate::NativeEnum) for the enum, using its name as
+/// the name of the enum in the game, and the conversions needed to use it as an argument or
+/// a return value of exported functions.
+///
+/// The enum has to implement [`Default`]. Values received from the game that don't match any
+/// of the variants are logged and converted into the default variant. The discriminants must
+/// fit in an `i64`, larger `u64` values are rejected at compile time.
+#[macro_export]
+macro_rules! native_enum {
+    (
+        $(#[$meta:meta])*
+        $vis:vis enum $name:ident: $repr:ident {
+            $($(#[$vmeta:meta])* $variant:ident = $value:expr),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[repr($repr)]
+        $vis enum $name {
+            $($(#[$vmeta])* $variant = $value),*
+        }
+
+        const _: () = {
+            $(assert!(
+                $name::$variant as i128 <= i64::MAX as i128,
+                concat!(
+                    "the discriminant of ",
+                    stringify!($name),
+                    "::",
+                    stringify!($variant),
+                    " does not fit in an i64"
+                )
+            );)*
+        };
+
+        unsafe impl $crate::NativeEnum for $name {
+            const NAME: &'static str = stringify!($name);
+            const VARIANTS: &'static [(&'static str, i64)] =
+                &[$((stringify!($variant), $name::$variant as i64)),*];
+
+            type Raw = $repr;
+
+            #[inline]
+            fn from_raw(raw: $repr) -> ::std::option::Option<Self> {
+                $(if raw == $name::$variant as $repr {
+                    return ::std::option::Option::Some($name::$variant);
+                })*
+                ::std::option::Option::None
+            }
+
+            #[inline]
+            fn into_raw(self) -> $repr {
+                self as $repr
+            }
+        }
+
+        impl $crate::FromRepr for $name {
+            type Repr = $crate::EnumRepr<$name>;
+
+            #[inline]
+            fn from_repr(repr: Self::Repr) -> Self {
+                repr.get_or_default()
+            }
+        }
+
+        impl $crate::IntoRepr for $name {
+            type Repr = $crate::EnumRepr<$name>;
+
+            #[inline]
+            fn into_repr(self) -> Self::Repr {
+                $crate::EnumRepr::from(self)
+            }
+        }
+    };
+}
+
+/// Defines a set of flags that can be exported with [`BitfieldExport`]. The flags are stored in
+/// an unsigned integer, and each flag is declared along with the position of its bit.
+/// Implements [`NativeRepr`] and [`NativeBitfield`](crate::NativeBitfield) for the type, using its
+/// name as the name of the bitfield in the game, and the bitwise operators for combining flags.
+/// Flag sets used as arguments or return values of exported functions also need to implement
+/// [`Default`].
+#[macro_export]
+macro_rules! native_bitfield {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident: $repr:ty {
+            $($(#[$fmeta:meta])* const $flag:ident = $bit:expr;)*
+        }
+    ) => {
+        $(#[$meta])*
+        #[repr(transparent)]
+        $vis struct $name($repr);
+
+        #[allow(dead_code)]
+        impl $name {
+            $($(#[$fmeta])* pub const $flag: Self = Self(1 << $bit);)*
+
+            /// Returns a set with no flags.
+            #[inline]
+            pub const fn empty() -> Self {
+                Self(0)
+            }
+
+            /// Creates a set from raw bits, including bits that don't correspond to any flag.
+            #[inline]
+            pub const fn from_bits_retain(bits: $repr) -> Self {
+                Self(bits)
+            }
+
+            /// Returns the raw bits of the set.
+            #[inline]
+            pub const fn bits(&self) -> $repr {
+                self.0
+            }
+
+            /// Returns whether all flags of `other` are set.
+            #[inline]
+            pub const fn contains(&self, other: Self) -> bool {
+                self.0 & other.0 == other.0
+            }
+
+            /// Returns whether no flags are set.
+            #[inline]
+            pub const fn is_empty(&self) -> bool {
+                self.0 == 0
+            }
+
+            /// Sets the flags of `other`.
+            #[inline]
+            pub fn insert(&mut self, other: Self) {
+                self.0 |= other.0;
+            }
+
+            /// Clears the flags of `other`.
+            #[inline]
+            pub fn remove(&mut self, other: Self) {
+                self.0 &= !other.0;
+            }
+        }
+
+        impl ::std::ops::BitOr for $name {
+            type Output = Self;
+
+            #[inline]
+            fn bitor(self, rhs: Self) -> Self {
+                Self(self.0 | rhs.0)
+            }
+        }
+
+        impl ::std::ops::BitOrAssign for $name {
+            #[inline]
+            fn bitor_assign(&mut self, rhs: Self) {
+                self.0 |= rhs.0;
+            }
+        }
+
+        impl ::std::ops::BitAnd for $name {
+            type Output = Self;
+
+            #[inline]
+            fn bitand(self, rhs: Self) -> Self {
+                Self(self.0 & rhs.0)
+            }
+        }
+
+        impl ::std::ops::BitAndAssign for $name {
+            #[inline]
+            fn bitand_assign(&mut self, rhs: Self) {
+                self.0 &= rhs.0;
+            }
+        }
+
+        unsafe impl $crate::NativeRepr for $name {
+            const NAME: &'static str = stringify!($name);
+        }
+
+        unsafe impl $crate::NativeBitfield for $name {
+            const BITS: &'static [(&'static str, u8)] = &[$((stringify!($flag), $bit)),*];
+        }
+    };
+}
+
 /// Define a list of methods to register with the game. Usually used in conjuction with
 /// [`exports!`].
 #[macro_export]
@@ -338,6 +1369,15 @@ macro_rules! methods {
     };
 }
 
+/// Define a list of struct instance methods to register with the game. The methods can take
+/// either `&self` or `&mut self`. Usually used in conjuction with [`exports!`].
+#[macro_export]
+macro_rules! struct_methods {
+    [$($name:literal => $ty:ident::$id:ident),*$(,)?] => {
+        const { &[$($crate::struct_method!($name, $ty::$id)),*] }
+    };
+}
+
 /// Define a list of static methods to register with the game. Usually used in conjuction with
 /// [`exports!`].
 #[macro_export]
@@ -346,3 +1386,65 @@ macro_rules! static_methods {
         const { &[$($crate::global!($($mod)* $name, $ty::$id)),*] }
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::registration_order;
+    use crate::{EnumRepr, FromRepr, IntoRepr, NativeEnum};
+
+    native_enum! {
+        #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+        enum Mode: i32 {
+            #[default]
+            Idle = 0,
+            Walking = 1,
+            Running = 4,
+        }
+    }
+
+    #[test]
+    fn registration_order_keeps_independent_exports() {
+        let nodes = [
+            (Some("A"), None),
+            (Some("B"), Some("IScriptable")),
+            (None, None),
+        ];
+        assert_eq!(registration_order(&nodes), [0, 1, 2]);
+    }
+
+    #[test]
+    fn registration_order_moves_bases_first() {
+        let nodes = [
+            (Some("C"), Some("B")),
+            (Some("B"), Some("A")),
+            (Some("D"), None),
+            (Some("A"), Some("IScriptable")),
+        ];
+        assert_eq!(registration_order(&nodes), [3, 1, 0, 2]);
+    }
+
+    #[test]
+    fn registration_order_with_cycle() {
+        let nodes = [
+            (Some("A"), Some("B")),
+            (Some("B"), Some("A")),
+            (Some("C"), Some("C")),
+        ];
+        assert_eq!(registration_order(&nodes), [1, 0, 2]);
+    }
+
+    #[test]
+    fn native_enum_converts_declared_variants() {
+        assert_eq!(Mode::from_raw(1), Some(Mode::Walking));
+        assert_eq!(Mode::from_raw(4), Some(Mode::Running));
+        assert_eq!(Mode::Running.into_repr().raw(), 4);
+        assert_eq!(Mode::from_repr(EnumRepr::new(4)), Mode::Running);
+    }
+
+    #[test]
+    fn native_enum_rejects_undeclared_values() {
+        assert_eq!(Mode::from_raw(2), None);
+        assert_eq!(Mode::from_raw(-1), None);
+        assert_eq!(Mode::from_repr(EnumRepr::new(3)), Mode::Idle);
+    }
+}
@@ -0,0 +1,273 @@
+use std::str::FromStr;
+use std::{env, fmt};
+
+use thiserror::Error;
+
+/// A severity level of a log record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LogLevel {
+    Error = 1,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+/// The most verbose [`LogLevel`] allowed by a [`LogFilter`] directive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LevelFilter {
+    Off,
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LevelFilter {
+    #[inline]
+    pub fn allows(self, level: LogLevel) -> bool {
+        level as u8 <= self as u8
+    }
+}
+
+impl FromStr for LevelFilter {
+    type Err = LogFilterParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "off" => Ok(Self::Off),
+            "error" => Ok(Self::Error),
+            "warn" => Ok(Self::Warn),
+            "info" => Ok(Self::Info),
+            "debug" => Ok(Self::Debug),
+            "trace" => Ok(Self::Trace),
+            _ => Err(LogFilterParseError::InvalidLevel(s.to_owned())),
+        }
+    }
+}
+
+impl fmt::Display for LevelFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let str = match self {
+            Self::Off => "off",
+            Self::Error => "error",
+            Self::Warn => "warn",
+            Self::Info => "info",
+            Self::Debug => "debug",
+            Self::Trace => "trace",
+        };
+        f.write_str(str)
+    }
+}
+
+/// A filter applied to the records emitted through the `log` and `tracing` integrations
+/// before they're forwarded to the RED4ext logger. It can be set at runtime using
+/// [`SdkEnv::set_log_filter`](crate::SdkEnv::set_log_filter).
+///
+/// Filters are parsed from a comma-separated list of directives, each being either a level
+/// applied to all targets, or a `target=level` pair applied to a module path and all of its
+/// submodules. The most specific directive matching a target wins. A target without a level
+/// enables all records for that target.
+///
+/// # Example
+/// ```rust
+/// use red4ext_rs::{LogFilter, LogLevel};
+///
+/// let filter: LogFilter = "warn,my_plugin::net=trace".parse().unwrap();
+/// assert!(filter.enabled("my_plugin::net::http", LogLevel::Debug));
+/// assert!(!filter.enabled("my_plugin::ui", LogLevel::Info));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogFilter {
+    default: LevelFilter,
+    // sorted by the length of the target, longest first
+    directives: Vec<(String, LevelFilter)>,
+}
+
+impl LogFilter {
+    /// Creates a filter that applies the given level to all targets.
+    #[inline]
+    pub const fn new(default: LevelFilter) -> Self {
+        Self {
+            default,
+            directives: Vec::new(),
+        }
+    }
+
+    /// Adds a directive for a target and its submodules.
+    pub fn with_target(mut self, target: impl Into<String>, level: LevelFilter) -> Self {
+        let target = target.into();
+        self.directives.retain(|(t, _)| *t != target);
+        let index = self
+            .directives
+            .partition_point(|(t, _)| t.len() >= target.len());
+        self.directives.insert(index, (target, level));
+        self
+    }
+
+    /// Reads a filter from an environment variable. Returns [`None`] if the variable is not set.
+    pub fn from_env(var: &str) -> Option<Result<Self, LogFilterParseError>> {
+        env::var(var).ok().map(|str| str.parse())
+    }
+
+    /// Returns whether a record with the given target and level should be logged.
+    pub fn enabled(&self, target: &str, level: LogLevel) -> bool {
+        self.directives
+            .iter()
+            .find(|(t, _)| matches_target(t, target))
+            .map_or(self.default, |(_, filter)| *filter)
+            .allows(level)
+    }
+
+    /// Returns whether the filter has any target-specific directives.
+    #[inline]
+    pub(crate) fn has_directives(&self) -> bool {
+        !self.directives.is_empty()
+    }
+
+    /// Returns the most verbose level allowed by any of the directives.
+    pub fn max_level(&self) -> LevelFilter {
+        self.directives
+            .iter()
+            .map(|(_, level)| *level)
+            .fold(self.default, Ord::max)
+    }
+}
+
+impl Default for LogFilter {
+    #[inline]
+    fn default() -> Self {
+        Self::new(LevelFilter::Trace)
+    }
+}
+
+impl FromStr for LogFilter {
+    type Err = LogFilterParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut filter = Self::new(LevelFilter::Trace);
+        let mut default = None;
+        for directive in s.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+            match directive.split_once('=') {
+                Some((target, level)) => {
+                    let target = target.trim();
+                    if target.is_empty() {
+                        return Err(LogFilterParseError::InvalidDirective(directive.to_owned()));
+                    }
+                    filter = filter.with_target(target, level.trim().parse()?);
+                }
+                None => match directive.parse() {
+                    Ok(level) => default = Some(level),
+                    Err(_) => filter = filter.with_target(directive, LevelFilter::Trace),
+                },
+            }
+        }
+        // when only targets are specified, everything else is disabled
+        filter.default = default.unwrap_or(if filter.directives.is_empty() {
+            LevelFilter::Trace
+        } else {
+            LevelFilter::Off
+        });
+        Ok(filter)
+    }
+}
+
+impl fmt::Display for LogFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.default)?;
+        for (target, level) in self.directives.iter().rev() {
+            write!(f, ",{target}={level}")?;
+        }
+        Ok(())
+    }
+}
+
+fn matches_target(prefix: &str, target: &str) -> bool {
+    target
+        .strip_prefix(prefix)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+}
+
+/// An error returned when a [`LogFilter`] could not be parsed.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum LogFilterParseError {
+    #[error("invalid log level '{0}'")]
+    InvalidLevel(String),
+    #[error("invalid log filter directive '{0}'")]
+    InvalidDirective(String),
+}
+
+#[cfg(feature = "log")]
+impl From<log::Level> for LogLevel {
+    #[inline]
+    fn from(level: log::Level) -> Self {
+        match level {
+            log::Level::Error => Self::Error,
+            log::Level::Warn => Self::Warn,
+            log::Level::Info => Self::Info,
+            log::Level::Debug => Self::Debug,
+            log::Level::Trace => Self::Trace,
+        }
+    }
+}
+
+#[cfg(feature = "log")]
+impl From<LevelFilter> for log::LevelFilter {
+    #[inline]
+    fn from(filter: LevelFilter) -> Self {
+        match filter {
+            LevelFilter::Off => Self::Off,
+            LevelFilter::Error => Self::Error,
+            LevelFilter::Warn => Self::Warn,
+            LevelFilter::Info => Self::Info,
+            LevelFilter::Debug => Self::Debug,
+            LevelFilter::Trace => Self::Trace,
+        }
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl From<tracing::Level> for LogLevel {
+    #[inline]
+    fn from(level: tracing::Level) -> Self {
+        match level {
+            tracing::Level::ERROR => Self::Error,
+            tracing::Level::WARN => Self::Warn,
+            tracing::Level::INFO => Self::Info,
+            tracing::Level::DEBUG => Self::Debug,
+            _ => Self::Trace,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LevelFilter, LogFilter, LogFilterParseError, LogLevel};
+
+    #[test]
+    fn parse_filter() {
+        let filter: LogFilter = "info, plugin::net=trace, plugin=warn".parse().unwrap();
+        assert!(filter.enabled("other", LogLevel::Info));
+        assert!(!filter.enabled("other", LogLevel::Debug));
+        assert!(filter.enabled("plugin::net::http", LogLevel::Trace));
+        assert!(!filter.enabled("plugin::ui", LogLevel::Info));
+        assert!(filter.enabled("plugin", LogLevel::Warn));
+        assert!(!filter.enabled("plugin_other", LogLevel::Debug));
+        assert_eq!(filter.max_level(), LevelFilter::Trace);
+        assert_eq!(filter.to_string(), "info,plugin=warn,plugin::net=trace");
+
+        let filter: LogFilter = "plugin".parse().unwrap();
+        assert!(filter.enabled("plugin::ui", LogLevel::Trace));
+        assert!(!filter.enabled("other", LogLevel::Error));
+
+        assert_eq!(
+            "plugin=loud".parse::<LogFilter>(),
+            Err(LogFilterParseError::InvalidLevel("loud".to_owned()))
+        );
+        assert_eq!(
+            "=info".parse::<LogFilter>(),
+            Err(LogFilterParseError::InvalidDirective("=info".to_owned()))
+        );
+    }
+}
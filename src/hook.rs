@@ -0,0 +1,249 @@
+use std::sync::Mutex;
+use std::{fmt, mem};
+
+use thiserror::Error;
+
+use crate::types::{CName, Class, Function, Method};
+use crate::{RttiSystem, SdkEnv, VoidPtr, addr_hashes, lock};
+
+/// The number of arguments accepted by native function handlers.
+const NATIVE_HANDLER_ARITY: usize = 4;
+
+/// A guard for a hook attached with [`SdkEnv::attach_hook_guarded`], or wrapped with
+/// [`HookGuard::new`]. The hook is detached when the guard is dropped, unless it's leaked with
+/// [`HookGuard::leak`].
+#[must_use = "the hook is detached when the guard is dropped"]
+pub struct HookGuard<'a> {
+    env: &'a SdkEnv,
+    hook: ActiveHook,
+}
+
+impl<'a> HookGuard<'a> {
+    /// Creates a guard for a hook attached by the plugin, which detaches the hook when dropped.
+    #[inline]
+    pub fn new(env: &'a SdkEnv, hook: ActiveHook) -> Self {
+        Self { env, hook }
+    }
+
+    /// Returns the information about the attached hook.
+    #[inline]
+    pub fn hook(&self) -> &ActiveHook {
+        &self.hook
+    }
+
+    /// Consumes the guard without detaching the hook. The hook will stay attached until it's
+    /// detached with [`SdkEnv::detach_hook`] or the plugin is unloaded.
+    #[inline]
+    pub fn leak(self) {
+        mem::forget(self);
+    }
+}
+
+impl Drop for HookGuard<'_> {
+    fn drop(&mut self) {
+        self.env.detach_registered(self.hook.target);
+    }
+}
+
+impl fmt::Debug for HookGuard<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("HookGuard").field(&self.hook).finish()
+    }
+}
+
+/// Information about a hook that is currently attached by the plugin.
+/// A list of them can be retrieved with [`SdkEnv::active_hooks`].
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct ActiveHook {
+    name: &'static str,
+    target: usize,
+    hook: usize,
+}
+
+impl ActiveHook {
+    #[inline]
+    pub(crate) fn new(name: &'static str, target: VoidPtr, hook: VoidPtr) -> Self {
+        Self {
+            name,
+            target: target as usize,
+            hook: hook as usize,
+        }
+    }
+
+    /// Returns the name of the hook, as declared in the [`hooks!`](crate::hooks) macro.
+    #[inline]
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Returns the address of the hooked function.
+    #[inline]
+    pub fn target(&self) -> VoidPtr {
+        self.target as VoidPtr
+    }
+}
+
+impl fmt::Debug for ActiveHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ActiveHook")
+            .field("name", &self.name)
+            .field("target", &format_args!("{:#x}", self.target))
+            .finish()
+    }
+}
+
+/// A target of a hook declared in the [`hooks!`](crate::hooks) macro. The target is resolved
+/// when the hook is attached with [`SdkEnv::attach_hook_resolved`].
+///
+/// A target can be created from an [`addr_hashes`] constant, or from the full name of an RTTI
+/// native function. Methods are specified as `"Class::Method"`, where the class can be given
+/// either by its native or its script name. The method can be given by its short name, unless
+/// it's overloaded, in which case its full name including the mangled parameter types is required.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookTarget {
+    /// A hash of a function address.
+    Hash(u32),
+    /// A full name of an RTTI native function.
+    Native(&'static str),
+}
+
+impl HookTarget {
+    /// Resolves the address of the target function.
+    pub fn resolve(self) -> Result<VoidPtr, HookError> {
+        match self {
+            Self::Hash(hash) => match addr_hashes::resolve(hash) {
+                0 => Err(HookError::UnresolvedHash(hash)),
+                addr => Ok(addr as VoidPtr),
+            },
+            Self::Native(name) => {
+                let rtti = RttiSystem::get();
+                let func = match name.split_once("::") {
+                    Some((class, method)) => rtti
+                        .get_class(CName::new(class))
+                        .or_else(|| rtti.get_class_by_script_name(CName::new(class)))
+                        .ok_or(HookError::UnresolvedFunction(name))
+                        .and_then(|class| find_method(class, CName::new(method), name))?,
+                    None => rtti
+                        .get_function(CName::new(name))
+                        .ok_or(HookError::UnresolvedFunction(name))?,
+                };
+                func.native_handler().ok_or(HookError::NotNative(name))
+            }
+        }
+    }
+
+    /// Returns the number of arguments the target function is known to accept.
+    ///
+    /// This is the only part of the signature that can be verified. Native functions are
+    /// always invoked through handlers accepting the same four arguments, whatever the
+    /// parameters of the scripted function are, and nothing is known about the functions
+    /// behind hashes.
+    #[inline]
+    pub(crate) fn arity(self) -> Option<usize> {
+        match self {
+            Self::Hash(_) => None,
+            Self::Native(_) => Some(NATIVE_HANDLER_ARITY),
+        }
+    }
+}
+
+impl From<u32> for HookTarget {
+    #[inline]
+    fn from(hash: u32) -> Self {
+        Self::Hash(hash)
+    }
+}
+
+impl From<&'static str> for HookTarget {
+    #[inline]
+    fn from(name: &'static str) -> Self {
+        Self::Native(name)
+    }
+}
+
+/// Finds a method or a static method of the class, by its full name or its short name.
+/// Short names that match several overloads are rejected.
+fn find_method<'a>(
+    class: &'a Class,
+    name: CName,
+    target: &'static str,
+) -> Result<&'a Function, HookError> {
+    let candidates = match class.get_method(name) {
+        Ok(method) => return Ok(method.as_function()),
+        Err(candidates) => candidates.map(Method::as_function).collect::<Vec<_>>(),
+    };
+    let statics = class
+        .static_methods()
+        .iter()
+        .map(|method| method.as_function());
+    if let Some(func) = statics.clone().find(|func| func.name() == name) {
+        return Ok(func);
+    }
+    let statics = statics.filter(|func| func.short_name() == name);
+
+    let mut candidates = candidates.into_iter().chain(statics);
+    let first = candidates
+        .next()
+        .ok_or(HookError::UnresolvedFunction(target))?;
+    // methods overriding each other in the class hierarchy share the same full name
+    if candidates.any(|func| func.name() != first.name()) {
+        return Err(HookError::AmbiguousFunction(target));
+    }
+    Ok(first)
+}
+
+/// An error returned when a hook could not be attached.
+#[derive(Debug, Error)]
+pub enum HookError {
+    #[error("hook {0} is already attached or its target is already hooked")]
+    AlreadyAttached(&'static str),
+    #[error("failed to attach hook {0}")]
+    AttachFailed(&'static str),
+    #[error("hook {0} was declared without a target")]
+    MissingTarget(&'static str),
+    #[error("failed to resolve the address for hash {0}")]
+    UnresolvedHash(u32),
+    #[error("could not find function {0}")]
+    UnresolvedFunction(&'static str),
+    #[error("function {0} is overloaded, it should be referred to by its full name")]
+    AmbiguousFunction(&'static str),
+    #[error("function {0} is not native")]
+    NotNative(&'static str),
+    #[error("hook {hook} expects {actual} arguments, but its target accepts {expected}")]
+    SignatureMismatch {
+        hook: &'static str,
+        expected: usize,
+        actual: usize,
+    },
+}
+
+/// A registry of the hooks attached by the plugin, owned by [`SdkEnv`].
+#[derive(Debug, Default)]
+pub(crate) struct HookRegistry(Mutex<Vec<ActiveHook>>);
+
+impl HookRegistry {
+    /// Reserves an entry for the hook, failing if either the hook or its target is already
+    /// in use.
+    pub(crate) fn insert(&self, hook: ActiveHook) -> Result<(), HookError> {
+        let mut hooks = lock(&self.0);
+        if hooks
+            .iter()
+            .any(|h| h.target == hook.target || h.hook == hook.hook)
+        {
+            return Err(HookError::AlreadyAttached(hook.name));
+        }
+        hooks.push(hook);
+        Ok(())
+    }
+
+    pub(crate) fn remove(&self, target: usize) -> Option<ActiveHook> {
+        let mut hooks = lock(&self.0);
+        let index = hooks.iter().position(|h| h.target == target)?;
+        Some(hooks.swap_remove(index))
+    }
+
+    #[inline]
+    pub(crate) fn list(&self) -> Vec<ActiveHook> {
+        lock(&self.0).clone()
+    }
+}
@@ -1,6 +1,8 @@
 use std::ffi::CStr;
 use std::marker::PhantomData;
 use std::mem::MaybeUninit;
+use std::panic::{self, AssertUnwindSafe};
+use std::thread;
 
 use sealed::sealed;
 use thiserror::Error;
@@ -11,7 +13,7 @@ use crate::types::{
     CName, Class, Function, FunctionFlags, FunctionHandler, GlobalFunction, IScriptable, Method,
     PoolRef, Ref, ScriptRef, StackArg, StackFrame, StaticMethod,
 };
-use crate::{ScriptClass, VoidPtr};
+use crate::{ENV, ScriptClass, VoidPtr, class_kind};
 
 /// An error returned when invoking a function fails.
 #[derive(Debug, Error)]
@@ -78,7 +80,14 @@ impl InvokeError {
 pub trait GlobalInvocable<A, R> {
     const FN_TYPE: FunctionType;
 
-    fn invoke(self, ctx: &IScriptable, frame: &mut StackFrame, ret: Option<&mut MaybeUninit<R>>);
+    /// Invokes the function with arguments read from the stack frame. If the function panics,
+    /// a default value is written to the return slot and the panic payload is returned.
+    fn invoke(
+        self,
+        ctx: &IScriptable,
+        frame: &mut StackFrame,
+        ret: Option<&mut MaybeUninit<R>>,
+    ) -> thread::Result<()>;
 }
 
 macro_rules! impl_global_invocable {
@@ -90,7 +99,8 @@ macro_rules! impl_global_invocable {
             where
                 Func: Fn($($types,)*) -> R,
                 $($types: FromRepr, $types::Repr: Default,)*
-                R: IntoRepr
+                R: IntoRepr,
+                R::Repr: Default,
             {
                 const FN_TYPE: FunctionType = FunctionType {
                     args: &[$(CName::new($types::Repr::NAME),)*],
@@ -98,12 +108,13 @@ macro_rules! impl_global_invocable {
                 };
 
                 #[inline]
-                fn invoke(self, _ctx: &IScriptable, frame: &mut StackFrame, ret: Option<&mut MaybeUninit<R::Repr>>) {
-                    $(let $types = unsafe { frame.get_arg::<$types>() };)*
-                    let res = self($($types,)*);
-                    if let Some(ret) = ret {
-                        unsafe { ret.as_mut_ptr().write(res.into_repr()) };
-                    }
+                fn invoke(self, _ctx: &IScriptable, frame: &mut StackFrame, ret: Option<&mut MaybeUninit<R::Repr>>) -> thread::Result<()> {
+                    // the arguments are read inside the guard, since their conversions might panic
+                    let res = panic::catch_unwind(AssertUnwindSafe(|| {
+                        $(let $types = unsafe { frame.get_arg::<$types>() };)*
+                        self($($types,)*).into_repr()
+                    }));
+                    write_result(res, ret)
                 }
             }
         )*
@@ -131,7 +142,8 @@ macro_rules! impl_global_invocable_with_ref {
                 This: NativeRepr,
                 Func: for<'a> Fn(ScriptRef<'a, This>, $($types,)*) -> R,
                 $($types: FromRepr, $types::Repr: Default,)*
-                R: IntoRepr
+                R: IntoRepr,
+                R::Repr: Default,
             {
                 const FN_TYPE: FunctionType = FunctionType {
                     args: &[CName::new(<ScriptRef<'_, This>>::NAME), $(CName::new($types::Repr::NAME),)*],
@@ -139,13 +151,13 @@ macro_rules! impl_global_invocable_with_ref {
                 };
 
                 #[inline]
-                fn invoke(self, _ctx: &IScriptable, frame: &mut StackFrame, ret: Option<&mut MaybeUninit<R::Repr>>) {
-                    let this = unsafe { frame.get_arg() };
-                    $(let $types = unsafe { frame.get_arg::<$types>() };)*
-                    let res = self(this, $($types,)*);
-                    if let Some(ret) = ret {
-                        unsafe { ret.as_mut_ptr().write(res.into_repr()) };
-                    }
+                fn invoke(self, _ctx: &IScriptable, frame: &mut StackFrame, ret: Option<&mut MaybeUninit<R::Repr>>) -> thread::Result<()> {
+                    let res = panic::catch_unwind(AssertUnwindSafe(|| {
+                        let this = unsafe { frame.get_arg() };
+                        $(let $types = unsafe { frame.get_arg::<$types>() };)*
+                        self(this, $($types,)*).into_repr()
+                    }));
+                    write_result(res, ret)
                 }
             }
         )*
@@ -167,7 +179,14 @@ impl_global_invocable_with_ref!(
 pub trait MethodInvocable<Ctx, A, R> {
     const FN_TYPE: FunctionType;
 
-    fn invoke(self, ctx: &Ctx, frame: &mut StackFrame, ret: Option<&mut MaybeUninit<R>>);
+    /// Invokes the method with arguments read from the stack frame. If the method panics,
+    /// a default value is written to the return slot and the panic payload is returned.
+    fn invoke(
+        self,
+        ctx: &Ctx,
+        frame: &mut StackFrame,
+        ret: Option<&mut MaybeUninit<R>>,
+    ) -> thread::Result<()>;
 }
 
 macro_rules! impl_method_invocable {
@@ -179,7 +198,8 @@ macro_rules! impl_method_invocable {
             where
                 FN: Fn(&Ctx, $($types,)*) -> R,
                 $($types: FromRepr, $types::Repr: Default,)*
-                R: IntoRepr
+                R: IntoRepr,
+                R::Repr: Default,
             {
                 const FN_TYPE: FunctionType = FunctionType {
                     args: &[$(CName::new($types::Repr::NAME),)*],
@@ -187,12 +207,12 @@ macro_rules! impl_method_invocable {
                 };
 
                 #[inline]
-                fn invoke(self, ctx: &Ctx, frame: &mut StackFrame, ret: Option<&mut MaybeUninit<R::Repr>>) {
-                    $(let $types = unsafe { frame.get_arg::<$types>() };)*
-                    let res = self(ctx, $($types,)*);
-                    if let Some(ret) = ret {
-                        unsafe { ret.as_mut_ptr().write(res.into_repr()) };
-                    }
+                fn invoke(self, ctx: &Ctx, frame: &mut StackFrame, ret: Option<&mut MaybeUninit<R::Repr>>) -> thread::Result<()> {
+                    let res = panic::catch_unwind(AssertUnwindSafe(|| {
+                        $(let $types = unsafe { frame.get_arg::<$types>() };)*
+                        self(ctx, $($types,)*).into_repr()
+                    }));
+                    write_result(res, ret)
                 }
             }
         )*
@@ -210,6 +230,93 @@ impl_method_invocable!(
     (A, B, C, D, E, F, G)
 );
 
+/// A trait for functions that can be exported as struct methods. The methods are registered as
+/// static methods accepting a `script_ref` to the struct as their first argument, and they
+/// receive either `&This` or `&mut This`.
+#[sealed]
+pub trait StructMethodInvocable<This, A, R> {
+    const FN_TYPE: FunctionType;
+
+    /// Invokes the method with the receiver and arguments read from the stack frame. If the
+    /// method panics, a default value is written to the return slot and the panic payload is
+    /// returned. If the receiver is null, the method is not called, an error is logged and a
+    /// default value is written to the return slot.
+    fn invoke(self, frame: &mut StackFrame, ret: Option<&mut MaybeUninit<R>>)
+    -> thread::Result<()>;
+}
+
+macro_rules! impl_struct_method_invocable {
+    ($( ($( $types:ident ),*) ),*) => {
+        $(
+            impl_struct_method_invocable!(@impl &, $($types),*);
+            impl_struct_method_invocable!(@impl &mut, $($types),*);
+        )*
+    };
+    (@impl & $($mut:ident)?, $($types:ident),*) => {
+        #[allow(non_snake_case, unused_variables)]
+        #[sealed]
+        impl<This, $($types,)* R, FN> StructMethodInvocable<This, for<'a> fn(&'a $($mut)? This, $($types,)*), R::Repr> for FN
+        where
+            This: NativeRepr,
+            FN: for<'a> Fn(&'a $($mut)? This, $($types,)*) -> R,
+            $($types: FromRepr, $types::Repr: Default,)*
+            R: IntoRepr,
+            R::Repr: Default,
+        {
+            const FN_TYPE: FunctionType = FunctionType {
+                args: &[CName::new(<ScriptRef<'_, This>>::NAME), $(CName::new($types::Repr::NAME),)*],
+                ret: CName::new(R::Repr::NAME)
+            };
+
+            #[inline]
+            fn invoke(self, frame: &mut StackFrame, ret: Option<&mut MaybeUninit<R::Repr>>) -> thread::Result<()> {
+                let res = panic::catch_unwind(AssertUnwindSafe(|| {
+                    let mut this: ScriptRef<'_, This> = unsafe { frame.get_arg() };
+                    $(let $types = unsafe { frame.get_arg::<$types>() };)*
+                    let Some(this) = this.value_mut() else {
+                        if let Some(env) = ENV.get() {
+                            env.error(format_args!(
+                                "struct method of {} called with a null receiver",
+                                This::NAME
+                            ));
+                        }
+                        return R::Repr::default();
+                    };
+                    self(this, $($types,)*).into_repr()
+                }));
+                write_result(res, ret)
+            }
+        }
+    };
+}
+
+impl_struct_method_invocable!(
+    (),
+    (A),
+    (A, B),
+    (A, B, C),
+    (A, B, C, D),
+    (A, B, C, D, E),
+    (A, B, C, D, E, F)
+);
+
+/// Writes the result of an invocation to the return slot. If the invocation panicked, a default
+/// value is written instead.
+#[inline]
+fn write_result<R: Default>(
+    res: thread::Result<R>,
+    ret: Option<&mut MaybeUninit<R>>,
+) -> thread::Result<()> {
+    let (value, res) = match res {
+        Ok(value) => (value, Ok(())),
+        Err(payload) => (R::default(), Err(payload)),
+    };
+    if let Some(ret) = ret {
+        unsafe { ret.as_mut_ptr().write(value) };
+    }
+    res
+}
+
 /// A representation of a function type, including its arguments and return type.
 #[derive(Debug)]
 pub struct FunctionType {
@@ -330,9 +437,50 @@ impl<Ctx: ScriptClass> MethodMetadata<Ctx> {
     }
 }
 
+/// A representation of a struct method, including its name, a function handler, and its type.
+#[derive(Debug)]
+pub struct StructMethodMetadata<This> {
+    name: &'static CStr,
+    func: FunctionHandler<IScriptable, VoidPtr>,
+    typ: FunctionType,
+    parent: PhantomData<fn() -> *const This>,
+}
+
+impl<This> StructMethodMetadata<This> {
+    #[doc(hidden)]
+    #[inline]
+    pub const fn new<F: StructMethodInvocable<This, A, R>, A, R>(
+        name: &'static CStr,
+        func: FunctionHandler<IScriptable, VoidPtr>,
+        _f: &F,
+    ) -> Self {
+        Self {
+            name,
+            func,
+            typ: F::FN_TYPE,
+            parent: PhantomData,
+        }
+    }
+
+    /// Converts this metadata into a [`StaticMethod`] instance, which can be registered with
+    /// [RttiSystemMut](crate::RttiSystemMut).
+    pub fn to_rtti(&self, class: &Class) -> PoolRef<StaticMethod> {
+        let mut flags = FunctionFlags::default();
+        flags.set_is_native(true);
+        flags.set_is_final(true);
+        flags.set_is_static(true);
+
+        let mut func = StaticMethod::new(self.name, self.name, class, self.func, flags);
+        self.typ.initialize_func(func.as_function_mut());
+        func
+    }
+}
+
 /// A macro for defining global functions. Usually used in conjunction with the
 /// [`exports!`](crate::exports) macro.
 ///
+/// If the function panics, the panic is logged and a default value is returned to the caller.
+///
 /// # Example
 /// ```rust
 /// use red4ext_rs::{GlobalInvocable, GlobalMetadata, global};
@@ -355,7 +503,9 @@ macro_rules! global {
             _unk: i64,
         ) {
             let out = unsafe { std::mem::transmute(ret) };
-            $crate::GlobalInvocable::invoke($fun, ctx, frame, out);
+            if let Err(payload) = $crate::GlobalInvocable::invoke($fun, ctx, frame, out) {
+                $crate::internal::report_panic($name.to_string_lossy(), &*payload);
+            }
             unsafe { frame.step() };
         }
 
@@ -365,6 +515,12 @@ macro_rules! global {
 
 /// A macro for defining class methods. Usually used in conjunction with the
 /// [`methods!`](crate::methods) macro.
+///
+/// If the method panics, the panic is logged and a default value is returned to the caller.
+///
+/// Unless the method is marked as `final`, it can be overridden in scripts. Use the
+/// [`virtual_call!`](crate::virtual_call) macro to call it from Rust in a way that respects
+/// the overrides.
 #[macro_export]
 macro_rules! method {
     ($name:literal, $ty:ident::$id:ident $($mods:ident)*) => {{
@@ -375,7 +531,9 @@ macro_rules! method {
             _unk: i64,
         ) {
             let out = unsafe { ::std::mem::transmute(ret) };
-            $crate::MethodInvocable::invoke($ty::$id, ctx, frame, out);
+            if let Err(payload) = $crate::MethodInvocable::invoke($ty::$id, ctx, frame, out) {
+                $crate::internal::report_panic($name.to_string_lossy(), &*payload);
+            }
             unsafe { frame.step() };
         }
 
@@ -390,6 +548,31 @@ macro_rules! method {
     }
 }
 
+/// A macro for defining struct methods. Usually used in conjunction with the
+/// [`struct_methods!`](crate::struct_methods) macro.
+///
+/// If the method panics, or if it's called with a null receiver, an error is logged and a
+/// default value is returned to the caller.
+#[macro_export]
+macro_rules! struct_method {
+    ($name:literal, $ty:ident::$id:ident) => {{
+        extern "C" fn native_impl(
+            _ctx: &$crate::types::IScriptable,
+            frame: &mut $crate::types::StackFrame,
+            ret: $crate::VoidPtr,
+            _unk: i64,
+        ) {
+            let out = unsafe { ::std::mem::transmute(ret) };
+            if let Err(payload) = $crate::StructMethodInvocable::invoke($ty::$id, frame, out) {
+                $crate::internal::report_panic($name.to_string_lossy(), &*payload);
+            }
+            unsafe { frame.step() };
+        }
+
+        $crate::StructMethodMetadata::<$ty>::new($name, native_impl, &$ty::$id)
+    }};
+}
+
 /// A macro for conveniently calling functions and methods.
 /// If you're calling a method, the first argument should be the instance of the class.
 /// The next argument should be a full function name, which might have to include mangled names of
@@ -451,6 +634,73 @@ macro_rules! call {
     };
 }
 
+/// A macro for calling a method of an exported class that can be overridden in scripts.
+/// If the runtime class of the instance overrides the method in scripts, the override is invoked,
+/// otherwise the provided Rust implementation is called directly. The macro evaluates to a
+/// [`Result`] with an [`InvokeError`] if the scripted override could not be invoked.
+///
+/// The method is referred to by the name it was exported with. Only scripted methods with the
+/// same parameter and return types are considered overrides, other overloads are ignored.
+///
+/// The Rust implementation is the one that should be exported with [`methods!`](crate::methods),
+/// since a scripted override calling `super` would otherwise dispatch back to itself.
+///
+/// # Example
+/// ```rust
+/// use red4ext_rs::types::IScriptable;
+/// use red4ext_rs::{ClassExport, ScriptClass, class_kind, methods, virtual_call};
+///
+/// #[derive(Debug, Default, Clone)]
+/// #[repr(C)]
+/// struct Greeter {
+///     base: IScriptable,
+/// }
+///
+/// impl Greeter {
+///     // dispatches to a scripted override of `Greet` if there is one
+///     fn greet(&self, name: String) -> String {
+///         virtual_call!(self, "Greet" (name) -> String, Self::default_greet).unwrap_or_default()
+///     }
+///
+///     fn default_greet(&self, name: String) -> String {
+///         format!("Hello, {name}!")
+///     }
+/// }
+///
+/// unsafe impl ScriptClass for Greeter {
+///     const NAME: &'static str = "Greeter";
+///     type Kind = class_kind::Native;
+/// }
+///
+/// fn export() -> ClassExport<Greeter> {
+///     ClassExport::<Greeter>::builder()
+///         .methods(methods![c"Greet" => Greeter::default_greet])
+///         .build()
+/// }
+/// ```
+#[macro_export]
+macro_rules! virtual_call {
+    ($this:expr, $fn_name:literal ($( $args:expr ),*) -> $rett:ty, $default:expr) => {{
+        let this = $this;
+        let receiver = $crate::internal::native_receiver(this);
+        match receiver.get_script_override($crate::types::CName::new($fn_name)) {
+            ::std::option::Option::Some(method) => method.as_function().execute::<_, $rett>(
+                ::std::option::Option::Some(receiver),
+                ($( $crate::IntoRepr::into_repr($args), )*)
+            ),
+            ::std::option::Option::None => ::std::result::Result::Ok($default(this, $($args),*)),
+        }
+    }};
+}
+
+/// Returns the instance of a native class as [`IScriptable`], which all native classes
+/// start with.
+#[doc(hidden)]
+#[inline]
+pub fn native_receiver<C: ScriptClass<Kind = class_kind::Native>>(this: &C) -> &IScriptable {
+    unsafe { &*(this as *const C).cast::<IScriptable>() }
+}
+
 /// A trait for types that can be used as the receiver of a method call.
 #[sealed]
 pub trait AsReceiver {
@@ -1,34 +1,62 @@
 #![doc = include_str!("../README.md")]
 #![allow(clippy::missing_safety_doc)]
 use std::ffi::CString;
-use std::sync::OnceLock;
-use std::{ffi, fmt, mem};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::sync::{Mutex, MutexGuard, OnceLock, PoisonError, RwLock};
+use std::{cmp, ffi, fmt, hash, io, mem};
 
 pub use export::{
-    ClassExport, ClassExportBuilder, ExportList, ExportNil, Exportable, GlobalExport, StructExport,
+    BitfieldExport, ClassExport, ClassExportBuilder, ClassExtension, ClassExtensionBuilder,
+    EnumExport, ExportList, ExportNil, Exportable, FieldMetadata, GlobalExport, StructExport,
     StructExportBuilder,
 };
+pub use filter::{LevelFilter, LogFilter, LogFilterParseError, LogLevel};
+use hook::HookRegistry;
 use raw::root::{RED4ext as red, versioning};
+use scheduler::Scheduler;
 use sealed::sealed;
+use state::{PendingListeners, StateListenerTable};
+use thiserror::Error;
 use types::StaticArray;
+use widestring::U16CString;
 pub use widestring::{U16CStr, widecstr as wcstr};
 
 mod class;
 mod export;
+mod filter;
+mod hook;
 mod invocable;
+mod main_thread;
+mod paths;
 mod raw;
 mod repr;
+mod scheduler;
+mod state;
+mod storage;
+#[cfg(feature = "tracing")]
+mod subscriber;
 mod systems;
+mod unwind;
 
+#[cfg(feature = "config")]
+pub mod config;
+pub mod executor;
 /// A module encapsulating various types defined in the RED4ext SDK.
 pub mod types;
 
-pub use class::{ClassKind, ScriptClass, ScriptClassOps, class_kind};
+pub use class::{ClassKind, ScriptClass, ScriptClassOps, Subclass, class_kind};
+pub use hook::{ActiveHook, HookError, HookGuard, HookTarget};
 pub use invocable::{
     AsReceiver, FunctionType, GlobalInvocable, GlobalMetadata, InvokeError, MethodInvocable,
-    MethodMetadata,
+    MethodMetadata, StructMethodInvocable, StructMethodMetadata,
 };
-pub use repr::{FromRepr, IntoRepr, NativeRepr};
+pub use main_thread::{MainThread, MainThreadError, MainThreadTask};
+pub use repr::{EnumRepr, FromRepr, IntoRepr, NativeBitfield, NativeEnum, NativeRepr};
+pub use scheduler::{Schedule, Scheduler, TaskHandle};
+pub use state::ClosureListener;
+pub use storage::ObjectStorage;
 pub use systems::{RttiRegistrator, RttiSystem, RttiSystemMut};
 
 /// Hashes of known function addresses.
@@ -53,12 +81,16 @@ pub mod addr_hashes {
 
 #[doc(hidden)]
 pub mod internal {
+    pub use crate::invocable::native_receiver;
     pub use crate::red::{EMainReason, PluginHandle, PluginInfo, Sdk};
+    pub use crate::unwind::{catch_unwind, report_panic};
 }
 
 #[doc(hidden)]
 pub type VoidPtr = *mut std::os::raw::c_void;
 
+static ENV: OnceLock<Box<SdkEnv>> = OnceLock::new();
+
 /// A definition of a RED4ext plugin.
 pub trait Plugin {
     /// The name of the plugin.
@@ -71,6 +103,10 @@ pub trait Plugin {
     const SDK: SdkVersion = SdkVersion::LATEST;
     /// The version of the game the plugin is compatible with.
     const RUNTIME: RuntimeVersion = RuntimeVersion::RUNTIME_INDEPENDENT;
+    /// The ranges of game versions the plugin supports. If the game version reported by RED4ext
+    /// is not included in any of them, the plugin refuses to load and its exports are not
+    /// registered. An empty list means that all versions are supported.
+    const SUPPORTED_RUNTIMES: &'static [RuntimeVersionRange] = &[];
     /// The RED4ext API version.
     const API_VERSION: ApiVersion = ApiVersion::LATEST;
 
@@ -85,6 +121,16 @@ pub trait Plugin {
     ///
     /// For more informations, see [game's lifecycle](https://docs.red4ext.com/mod-developers/custom-game-states#games-life-cycle).
     fn on_load(_env: &SdkEnv) {}
+    /// A fallible variant of [`on_load`](Self::on_load), called when the plugin is loaded.
+    /// If it returns an error, the error is logged and RED4ext is told that the plugin failed to
+    /// load. In that case the exports are not registered and any hooks attached so far are
+    /// detached. The default implementation calls [`on_load`](Self::on_load).
+    ///
+    /// For more informations, see [game's lifecycle](https://docs.red4ext.com/mod-developers/custom-game-states#games-life-cycle).
+    fn try_on_load(env: &SdkEnv) -> Result<(), Box<dyn std::error::Error>> {
+        Self::on_load(env);
+        Ok(())
+    }
     /// A function that is called when the plugin is unloaded.
     ///
     /// For more informations, see [game's lifecycle](https://docs.red4ext.com/mod-developers/custom-game-states#games-life-cycle).
@@ -114,7 +160,7 @@ pub trait PluginOps: Plugin {
     #[doc(hidden)]
     fn info() -> PluginInfo;
     #[doc(hidden)]
-    fn load(env: SdkEnv);
+    fn load(env: SdkEnv) -> bool;
     #[doc(hidden)]
     fn unload(env: SdkEnv);
 }
@@ -130,7 +176,6 @@ where
 
     #[inline]
     fn env_lock() -> &'static OnceLock<Box<SdkEnv>> {
-        static ENV: OnceLock<Box<SdkEnv>> = OnceLock::new();
         &ENV
     }
 
@@ -145,7 +190,7 @@ where
         )
     }
 
-    fn load(env: SdkEnv) {
+    fn load(env: SdkEnv) -> bool {
         Self::env_lock()
             .set(Box::new(env))
             .expect("plugin environment should not be initialized");
@@ -153,14 +198,54 @@ where
         #[cfg(feature = "log")]
         {
             log::set_logger(Self::env()).unwrap();
-            log::set_max_level(log::LevelFilter::Trace);
+            log::set_max_level(Self::env().log_filter().max_level().into());
+        }
+        #[cfg(feature = "tracing")]
+        subscriber::install(Self::env());
+
+        let runtime = Self::env().runtime_version();
+        if !Self::SUPPORTED_RUNTIMES.is_empty()
+            && !Self::SUPPORTED_RUNTIMES
+                .iter()
+                .any(|r| r.contains(&runtime))
+        {
+            Self::env().error(format_args!(
+                "plugin does not support game version {runtime}, supported versions: {}",
+                Self::SUPPORTED_RUNTIMES
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+            return false;
         }
 
-        Self::on_load(Self::env());
+        MainThread::attach(Self::env());
+        match unwind::catch_unwind("Plugin::on_load", || Self::try_on_load(Self::env())) {
+            Some(Ok(())) => {
+                Self::env().finish_load();
+                true
+            }
+            Some(Err(err)) => {
+                Self::env().error(format_args!("plugin failed to load: {err}"));
+                Self::env().abort_load();
+                false
+            }
+            None => {
+                Self::env().abort_load();
+                false
+            }
+        }
     }
 
     fn unload(env: SdkEnv) {
-        Self::on_unload(&env);
+        unwind::catch_unwind("Plugin::on_unload", || Self::on_unload(&env));
+        MainThread::close();
+
+        if let Some(env) = Self::env_lock().get() {
+            env.detach_all_hooks();
+        }
+        unwind::catch_unwind("export unregistration", export::unregister_all);
     }
 }
 
@@ -189,15 +274,19 @@ macro_rules! export_plugin_symbols {
             ) -> bool {
                 match reason {
                     $crate::internal::EMainReason::Load => {
-                        <$trait as $crate::PluginOps>::load($crate::SdkEnv::new(handle, sdk));
-                        $crate::RttiRegistrator::add(Some(on_register), Some(on_post_register));
+                        let loaded =
+                            <$trait as $crate::PluginOps>::load($crate::SdkEnv::new(handle, sdk));
+                        if loaded {
+                            $crate::RttiRegistrator::add(Some(on_register), Some(on_post_register));
+                        }
+                        loaded
                     }
                     $crate::internal::EMainReason::Unload => {
                         <$trait as $crate::PluginOps>::unload($crate::SdkEnv::new(handle, sdk));
+                        true
                     }
-                    _ => {}
+                    _ => true,
                 }
-                true
             }
 
             #[unsafe(no_mangle)]
@@ -207,13 +296,17 @@ macro_rules! export_plugin_symbols {
             }
 
             extern "C" fn on_register() {
-                let exports = <$trait as $crate::Plugin>::exports();
-                $crate::Exportable::register(&exports);
+                $crate::internal::catch_unwind("Plugin::exports", || {
+                    let exports = <$trait as $crate::Plugin>::exports();
+                    $crate::Exportable::register(&exports);
+                });
             }
 
             extern "C" fn on_post_register() {
-                let exports = <$trait as $crate::Plugin>::exports();
-                $crate::Exportable::post_register(&exports);
+                $crate::internal::catch_unwind("Plugin::exports", || {
+                    let exports = <$trait as $crate::Plugin>::exports();
+                    $crate::Exportable::post_register(&exports);
+                });
             }
         }
     };
@@ -345,12 +438,34 @@ macro_rules! log_internal {
 pub struct SdkEnv {
     handle: red::PluginHandle,
     sdk: red::Sdk,
+    state_listeners: StateListenerTable,
+    pending_listeners: PendingListeners,
+    scheduler: Scheduler,
+    hooks: HookRegistry,
+    log_filter: RwLock<LogFilter>,
+    // cached properties of the filter, they allow most records to be checked without locking
+    log_max_level: AtomicU8,
+    log_targeted: AtomicBool,
+    #[cfg(feature = "config")]
+    config: OnceLock<config::AnyConfig>,
 }
 
 impl SdkEnv {
     #[doc(hidden)]
     pub fn new(handle: red::PluginHandle, sdk: red::Sdk) -> Self {
-        Self { handle, sdk }
+        Self {
+            handle,
+            sdk,
+            state_listeners: StateListenerTable::default(),
+            pending_listeners: PendingListeners::default(),
+            scheduler: Scheduler::default(),
+            hooks: HookRegistry::default(),
+            log_filter: RwLock::default(),
+            log_max_level: AtomicU8::new(LevelFilter::Trace as u8),
+            log_targeted: AtomicBool::new(false),
+            #[cfg(feature = "config")]
+            config: OnceLock::new(),
+        }
     }
 
     /// Logs a message at the info level.
@@ -388,10 +503,137 @@ impl SdkEnv {
         log_internal!(self, Trace, txt);
     }
 
+    /// Returns the directory containing the plugin DLL.
+    #[inline]
+    pub fn plugin_dir(&self) -> io::Result<PathBuf> {
+        let path = paths::module_path(self.handle)?;
+        path.parent()
+            .map(PathBuf::from)
+            .ok_or_else(|| io::Error::other("plugin path has no parent directory"))
+    }
+
+    /// Returns the root directory of the game installation.
+    #[inline]
+    pub fn game_dir(&self) -> io::Result<PathBuf> {
+        paths::game_dir()
+    }
+
+    /// Returns the `red4ext` directory in the game installation.
+    #[inline]
+    pub fn red4ext_dir(&self) -> io::Result<PathBuf> {
+        Ok(paths::game_dir()?.join("red4ext"))
+    }
+
+    /// Sets the filter applied to the records emitted through the `log` and `tracing`
+    /// integrations. By default all records are forwarded to the RED4ext logger.
+    /// The filter can be changed at any time, e.g. after reading it from a configuration file.
+    ///
+    /// # Example
+    /// ```rust
+    /// use red4ext_rs::{LevelFilter, LogFilter, SdkEnv};
+    ///
+    /// fn configure_logging(env: &SdkEnv) {
+    ///     match LogFilter::from_env("EXAMPLE_LOG") {
+    ///         Some(Ok(filter)) => env.set_log_filter(filter),
+    ///         Some(Err(err)) => env.error(format_args!("invalid log filter: {err}")),
+    ///         None => env.set_log_filter(LogFilter::new(LevelFilter::Info)),
+    ///     }
+    /// }
+    /// ```
+    pub fn set_log_filter(&self, filter: LogFilter) {
+        #[cfg(feature = "log")]
+        log::set_max_level(filter.max_level().into());
+        self.log_max_level
+            .store(filter.max_level() as u8, Ordering::Relaxed);
+        self.log_targeted
+            .store(filter.has_directives(), Ordering::Relaxed);
+        *self
+            .log_filter
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = filter;
+        #[cfg(feature = "tracing")]
+        tracing::callsite::rebuild_interest_cache();
+    }
+
+    /// Returns the filter applied to the records emitted through the `log` and `tracing`
+    /// integrations.
+    #[inline]
+    pub fn log_filter(&self) -> LogFilter {
+        self.log_filter
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    #[inline]
+    pub(crate) fn log_enabled(&self, target: &str, level: LogLevel) -> bool {
+        if level as u8 > self.log_max_level.load(Ordering::Relaxed) {
+            return false;
+        }
+        // without directives, the maximum level is the level of every target
+        if !self.log_targeted.load(Ordering::Relaxed) {
+            return true;
+        }
+        self.log_filter
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .enabled(target, level)
+    }
+
+    /// Returns the version of the game as reported by RED4ext.
+    #[inline]
+    pub fn runtime_version(&self) -> RuntimeVersion {
+        RuntimeVersion(unsafe { *self.sdk.runtime })
+    }
+
+    /// Adds a path to be loaded by the script compiler. The path can point to a single `.reds`
+    /// file or to a directory which will be searched for script files recursively.
+    /// Returns `false` if the path could not be added.
+    /// This should be called when the plugin is loaded, before the scripts are compiled.
+    ///
+    /// # Example
+    /// ```rust
+    /// use red4ext_rs::SdkEnv;
+    ///
+    /// fn add_scripts(env: &SdkEnv) {
+    ///     if !env.add_script_path(r"red4ext\plugins\example\scripts") {
+    ///         env.error("failed to add the script path");
+    ///     }
+    /// }
+    /// ```
+    pub fn add_script_path(&self, path: impl AsRef<Path>) -> bool {
+        let Ok(path) = U16CString::from_os_str(path.as_ref()) else {
+            return false;
+        };
+        unsafe { ((*self.sdk.scripts).Add.unwrap())(self.handle, path.as_ptr()) }
+    }
+
+    /// Registers a script type that should never be wrapped in a reference by the script
+    /// compiler. This is intended for native classes that are not meant to be used as `ref`.
+    /// Returns `false` if the type could not be registered.
+    pub fn register_never_ref_type(&self, name: &str) -> bool {
+        let Ok(name) = CString::new(name) else {
+            return false;
+        };
+        unsafe { ((*self.sdk.scripts).RegisterNeverRefType.unwrap())(name.as_ptr()) }
+    }
+
+    /// Registers a script type that can be used both with and without a reference by the script
+    /// compiler. Returns `false` if the type could not be registered.
+    pub fn register_mixed_ref_type(&self, name: &str) -> bool {
+        let Ok(name) = CString::new(name) else {
+            return false;
+        };
+        unsafe { ((*self.sdk.scripts).RegisterMixedRefType.unwrap())(name.as_ptr()) }
+    }
+
     /// Adds a listener to a specific state type.
     /// The listener will be called when the state is entered, updated, or exited.
     /// See [`StateType`] for the available state types.
     ///
+    /// Listeners added while the plugin is being loaded are registered once
+    /// [`Plugin::on_load`] has succeeded, and discarded if it fails.
+    ///
     /// # Example
     /// ```rust
     /// use red4ext_rs::{GameApp, SdkEnv, StateHandlerResult, StateListener, StateType};
@@ -414,14 +656,82 @@ impl SdkEnv {
     /// }
     /// ```
     #[inline]
-    pub fn add_listener(&self, typ: StateType, mut listener: StateListener) -> bool {
+    pub fn add_listener(&self, typ: StateType, listener: StateListener) -> bool {
+        match self.pending_listeners.defer(typ, listener) {
+            Ok(()) => true,
+            Err(listener) => self.register_listener(typ, listener),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn register_listener(&self, typ: StateType, mut listener: StateListener) -> bool {
         unsafe { ((*self.sdk.gameStates).Add.unwrap())(self.handle, typ as u32, &mut listener.0) }
     }
 
+    /// Adds a closure-based listener to a specific state type.
+    /// Unlike [`add_listener`](Self::add_listener), this method can be called at any time and
+    /// from any thread, and any number of listeners can be attached to the same state type.
+    /// The listeners are only dispatched for the environment returned by [`PluginOps::env`].
+    ///
+    /// Enter and exit callbacks are called until they return [`StateHandlerResult::Finished`],
+    /// and the state only transitions once all of them have finished. Update callbacks are
+    /// called every frame.
+    ///
+    /// # Example
+    /// ```rust
+    /// use red4ext_rs::{ClosureListener, SdkEnv, StateHandlerResult, StateType};
+    ///
+    /// fn add_closure_listener(env: &SdkEnv) {
+    ///     let mut frames = 0u64;
+    ///     let listener = ClosureListener::default().with_on_update(move |_app| {
+    ///         frames += 1;
+    ///         StateHandlerResult::Finished
+    ///     });
+    ///     env.add_closure_listener(StateType::Running, listener);
+    /// }
+    /// ```
+    #[inline]
+    pub fn add_closure_listener(&self, typ: StateType, listener: ClosureListener) {
+        self.state_listeners.add(typ, listener);
+    }
+
+    /// Retrieves the task scheduler of the plugin. The scheduler is driven by the update callback
+    /// of the [`Running`](StateType::Running) state and can be used to defer work to later
+    /// frames. See [`Scheduler`] for more information.
+    #[inline]
+    pub fn scheduler(&self) -> &Scheduler {
+        self.scheduler.attach(self);
+        &self.scheduler
+    }
+
+    /// Spawns a future on an executor polled by the update callback of the
+    /// [`Running`](StateType::Running) state. The future does not need to be [`Send`], it's
+    /// always polled on the game thread. Timer futures can be found in the [`executor`] module.
+    /// Returns a handle that can be used to cancel the future.
+    ///
+    /// # Panics
+    /// Panics if called from a thread other than the game thread.
+    #[inline]
+    pub fn spawn_local<F>(&self, future: F) -> TaskHandle
+    where
+        F: Future<Output = ()> + 'static,
+    {
+        executor::spawn_local(self, future)
+    }
+
     /// Attaches a hook to a target function.
     /// The hook will be called instead of the target function. The hook must accept a callback
     /// function as its last argument, which should be called to execute the original function.
     ///
+    /// This is the legacy way of attaching hooks, new code should prefer
+    /// [`SdkEnv::attach_hook_guarded`], which returns a [`HookGuard`] detaching the hook when
+    /// dropped. This function keeps returning a `bool`, so that existing callers which discard
+    /// the result keep their hooks attached instead of detaching them right away.
+    ///
+    /// The hook stays attached until it's detached with [`SdkEnv::detach_hook`], or until the
+    /// plugin is unloaded. Returns `false` if the hook could not be attached, use
+    /// [`SdkEnv::try_attach_hook`] to find out why.
+    ///
     /// # Safety
     /// The target and detour functions must both be valid and compatible function pointers.
     ///
@@ -442,36 +752,212 @@ impl SdkEnv {
     ///     cb(a, b)
     /// }
     /// ```
+    #[inline]
     pub unsafe fn attach_hook<F1, A1, R1, F2, A2, R2>(
         &self,
         hook: *mut Hook<F1, F2>,
         target: F1,
         detour: F2,
     ) -> bool
+    where
+        F1: FnPtr<A1, R1>,
+        F2: FnPtr<A2, R2>,
+    {
+        unsafe { self.try_attach_hook(hook, target, detour) }.is_ok()
+    }
+
+    /// Attaches a hook to a target function, like [`SdkEnv::attach_hook`], but returns an error
+    /// describing why the hook could not be attached. On success, returns the information about
+    /// the attached hook.
+    ///
+    /// # Safety
+    /// The target and detour functions must both be valid and compatible function pointers.
+    pub unsafe fn try_attach_hook<F1, A1, R1, F2, A2, R2>(
+        &self,
+        hook: *mut Hook<F1, F2>,
+        target: F1,
+        detour: F2,
+    ) -> Result<ActiveHook, HookError>
     where
         F1: FnPtr<A1, R1>,
         F2: FnPtr<A2, R2>,
     {
         unsafe {
-            let Hook(original, cb_ref, detour_ref) = &*hook;
+            let Hook(original, cb_ref, detour_ref, name, _) = &*hook;
+            let active = ActiveHook::new(*name, target.to_ptr(), hook.cast());
+            self.hooks.insert(active)?;
             detour_ref.replace(Some(detour));
 
-            ((*self.sdk.hooking).Attach.unwrap())(
+            let attached = ((*self.sdk.hooking).Attach.unwrap())(
                 self.handle,
                 target.to_ptr(),
                 original.to_ptr(),
                 (*cb_ref).cast::<VoidPtr>(),
-            )
+            );
+            if !attached {
+                detour_ref.replace(None);
+                self.hooks.remove(target.to_ptr() as usize);
+                return Err(HookError::AttachFailed(*name));
+            }
+            Ok(active)
+        }
+    }
+
+    /// Attaches a hook to a target function and returns a [`HookGuard`] which detaches the hook
+    /// when dropped. This is the preferred way of attaching hooks, the guard can be leaked with
+    /// [`HookGuard::leak`] to keep the hook attached until the plugin is unloaded.
+    ///
+    /// # Safety
+    /// The target and detour functions must both be valid and compatible function pointers.
+    ///
+    /// # Example
+    /// ```rust
+    /// use red4ext_rs::{HookError, HookGuard, SdkEnv, hooks};
+    ///
+    /// hooks! {
+    ///    static ADD_HOOK: fn(a: u32, b: u32) -> u32;
+    /// }
+    ///
+    /// fn attach_my_hook(
+    ///     env: &SdkEnv,
+    ///     addr: unsafe extern "C" fn(u32, u32) -> u32,
+    /// ) -> Result<HookGuard<'_>, HookError> {
+    ///     unsafe { env.attach_hook_guarded(ADD_HOOK, addr, detour) }
+    /// }
+    ///
+    /// unsafe extern "C" fn detour(a: u32, b: u32, cb: unsafe extern "C" fn(u32, u32) -> u32) -> u32 {
+    ///     // do something here...
+    ///     cb(a, b)
+    /// }
+    /// ```
+    #[inline]
+    pub unsafe fn attach_hook_guarded<F1, A1, R1, F2, A2, R2>(
+        &self,
+        hook: *mut Hook<F1, F2>,
+        target: F1,
+        detour: F2,
+    ) -> Result<HookGuard<'_>, HookError>
+    where
+        F1: FnPtr<A1, R1>,
+        F2: FnPtr<A2, R2>,
+    {
+        let active = unsafe { self.try_attach_hook(hook, target, detour) }?;
+        Ok(HookGuard::new(self, active))
+    }
+
+    /// Attaches a hook to the target it was declared with in the [`hooks!`] macro.
+    /// The target is resolved to an address first, see [`HookTarget`] for more information.
+    /// An error is returned if the hook has no target, if the target could not be resolved, or
+    /// if the target is a native function and the hook does not match the signature of native
+    /// function handlers. Only the number of arguments of the hook is checked, the types of the
+    /// arguments are not verified. Overloaded methods must be referred to by their full name.
+    ///
+    /// Like with [`SdkEnv::attach_hook`], the hook stays attached until it's detached or the
+    /// plugin is unloaded, it can be wrapped in a [`HookGuard`] to detach it automatically.
+    ///
+    /// # Safety
+    /// The signature of the hook must match the signature of the target function.
+    ///
+    /// # Example
+    /// ```rust
+    /// use red4ext_rs::types::{IScriptable, StackFrame};
+    /// use red4ext_rs::{HookError, SdkEnv, VoidPtr, hooks};
+    ///
+    /// hooks! {
+    ///    static ON_DEATH_HOOK: fn(
+    ///        i: *mut IScriptable,
+    ///        f: *mut StackFrame,
+    ///        a3: VoidPtr,
+    ///        a4: VoidPtr
+    ///    ) -> () = "PlayerPuppet::OnDeath";
+    /// }
+    ///
+    /// fn attach_my_hook(env: &SdkEnv) -> Result<(), HookError> {
+    ///     unsafe { env.attach_hook_resolved(ON_DEATH_HOOK, detour) }?;
+    ///     Ok(())
+    /// }
+    ///
+    /// unsafe extern "C" fn detour(
+    ///     i: *mut IScriptable,
+    ///     f: *mut StackFrame,
+    ///     a3: VoidPtr,
+    ///     a4: VoidPtr,
+    ///     cb: unsafe extern "C" fn(i: *mut IScriptable, f: *mut StackFrame, a3: VoidPtr, a4: VoidPtr),
+    /// ) {
+    ///     // do something here...
+    ///     cb(i, f, a3, a4)
+    /// }
+    /// ```
+    pub unsafe fn attach_hook_resolved<F1, A1, R1, F2, A2, R2>(
+        &self,
+        hook: *mut Hook<F1, F2>,
+        detour: F2,
+    ) -> Result<ActiveHook, HookError>
+    where
+        F1: FnPtr<A1, R1>,
+        F2: FnPtr<A2, R2>,
+    {
+        unsafe {
+            let Hook(_, _, _, name, target) = &*hook;
+            let target = target.ok_or(HookError::MissingTarget(*name))?;
+            let target = target();
+            match target.arity() {
+                Some(expected) if expected != F1::ARITY => {
+                    return Err(HookError::SignatureMismatch {
+                        hook: *name,
+                        expected,
+                        actual: F1::ARITY,
+                    });
+                }
+                _ => {}
+            }
+            let addr = target.resolve()?;
+            self.try_attach_hook(hook, F1::from_ptr(addr), detour)
         }
     }
 
-    /// Detaches a hook from a target function.
+    /// Detaches a hook from a target function. Returns `false` if there's no hook attached to
+    /// the target.
     #[inline]
     pub unsafe fn detach_hook<F, A, R>(&self, target: F) -> bool
     where
         F: FnPtr<A, R>,
     {
-        unsafe { ((*self.sdk.hooking).Detach.unwrap())(self.handle, target.to_ptr()) }
+        self.detach_registered(target.to_ptr() as usize)
+    }
+
+    /// Returns a list of the hooks that are currently attached by the plugin.
+    #[inline]
+    pub fn active_hooks(&self) -> Vec<ActiveHook> {
+        self.hooks.list()
+    }
+
+    /// Registers the listeners added while the plugin was being loaded.
+    pub(crate) fn finish_load(&self) {
+        for (typ, listener) in self.pending_listeners.close() {
+            self.register_listener(typ, listener);
+        }
+        StateListenerTable::install(self);
+    }
+
+    /// Undoes everything the plugin has set up while it was being loaded.
+    pub(crate) fn abort_load(&self) {
+        self.detach_all_hooks();
+        drop(self.pending_listeners.close());
+        self.state_listeners.clear();
+    }
+
+    pub(crate) fn detach_all_hooks(&self) {
+        for hook in self.hooks.list() {
+            self.detach_registered(hook.target() as usize);
+        }
+    }
+
+    pub(crate) fn detach_registered(&self, target: usize) -> bool {
+        if self.hooks.remove(target).is_none() {
+            return false;
+        }
+        unsafe { ((*self.sdk.hooking).Detach.unwrap())(self.handle, target as VoidPtr) }
     }
 }
 
@@ -480,11 +966,15 @@ unsafe impl Sync for SdkEnv {}
 
 #[cfg(feature = "log")]
 impl log::Log for SdkEnv {
-    fn enabled(&self, _metadata: &log::Metadata<'_>) -> bool {
-        true
+    #[inline]
+    fn enabled(&self, metadata: &log::Metadata<'_>) -> bool {
+        self.log_enabled(metadata.target(), metadata.level().into())
     }
 
     fn log(&self, record: &log::Record<'_>) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
         match record.level() {
             log::Level::Error => self.error(record.args()),
             log::Level::Warn => self.warn(record.args()),
@@ -498,7 +988,21 @@ impl log::Log for SdkEnv {
 }
 
 /// A version number in the semantic versioning format.
-#[derive(Debug)]
+/// The [`cargo_semver!`] macro can be used to create a version from the Cargo manifest.
+///
+/// # Example
+/// ```rust
+/// use red4ext_rs::{Prerelease, SemVer};
+///
+/// let version: SemVer = "1.2.0-beta.3".parse().unwrap();
+/// assert_eq!(
+///     version,
+///     SemVer::new(1, 2, 0).with_prerelease(Prerelease::Beta(3))
+/// );
+/// assert!(version < SemVer::new(1, 2, 0));
+/// assert_eq!(version.to_string(), "1.2.0-beta.3");
+/// ```
+#[derive(Debug, Clone, Copy)]
 pub struct SemVer(red::SemVer);
 
 impl SemVer {
@@ -518,6 +1022,260 @@ impl SemVer {
             prerelease: red::v0::SemVer_PrereleaseInfo { type_, number },
         })
     }
+
+    /// Returns a copy of this version with the specified prerelease information.
+    #[inline]
+    pub const fn with_prerelease(self, prerelease: Prerelease) -> Self {
+        let (type_, number) = prerelease.to_raw();
+        Self::exact(self.0.major, self.0.minor, self.0.patch, type_, number)
+    }
+
+    #[inline]
+    pub const fn major(&self) -> u8 {
+        self.0.major
+    }
+
+    #[inline]
+    pub const fn minor(&self) -> u16 {
+        self.0.minor
+    }
+
+    #[inline]
+    pub const fn patch(&self) -> u32 {
+        self.0.patch
+    }
+
+    /// Returns the prerelease information of this version.
+    /// Unknown prerelease types are reported as [`Prerelease::None`].
+    #[inline]
+    pub const fn prerelease(&self) -> Prerelease {
+        Prerelease::from_raw(self.0.prerelease.type_, self.0.prerelease.number)
+    }
+
+    #[doc(hidden)]
+    pub const fn from_cargo_parts(major: &str, minor: &str, patch: &str, pre: &str) -> Self {
+        const fn component(str: &str, max: u64) -> u64 {
+            match parse_decimal(str.as_bytes()) {
+                Some(value) if value <= max => value,
+                _ => panic!("version component should be a number in the supported range"),
+            }
+        }
+
+        let Some(prerelease) = Prerelease::parse(pre.as_bytes()) else {
+            panic!("prerelease should be empty or one of alpha, beta or rc followed by a number");
+        };
+        Self::new(
+            component(major, u8::MAX as u64) as u8,
+            component(minor, u16::MAX as u64) as u16,
+            component(patch, u32::MAX as u64) as u32,
+        )
+        .with_prerelease(prerelease)
+    }
+
+    #[inline]
+    fn key(&self) -> (u8, u16, u32, Prerelease) {
+        (self.0.major, self.0.minor, self.0.patch, self.prerelease())
+    }
+}
+
+impl PartialEq for SemVer {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for SemVer {}
+
+impl PartialOrd for SemVer {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SemVer {
+    #[inline]
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+impl hash::Hash for SemVer {
+    #[inline]
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.key().hash(state);
+    }
+}
+
+impl fmt::Display for SemVer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.0.major, self.0.minor, self.0.patch)?;
+        match self.prerelease() {
+            Prerelease::Alpha(n) => write!(f, "-alpha.{n}"),
+            Prerelease::Beta(n) => write!(f, "-beta.{n}"),
+            Prerelease::ReleaseCandidate(n) => write!(f, "-rc.{n}"),
+            Prerelease::None => Ok(()),
+        }
+    }
+}
+
+impl FromStr for SemVer {
+    type Err = VersionParseError;
+
+    /// Parses a version in the `major.minor.patch[-prerelease][+build]` format, where the
+    /// prerelease is one of `alpha`, `beta` or `rc`, optionally followed by a number, e.g.
+    /// `1.0.0-rc.2`. Build metadata is validated but ignored, since it does not affect the
+    /// precedence of versions.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let s = match s.split_once('+') {
+            Some((s, build)) if is_valid_build_metadata(build) => s,
+            Some((_, build)) => {
+                return Err(VersionParseError::InvalidBuildMetadata(build.to_owned()));
+            }
+            None => s,
+        };
+        let (version, pre) = s.split_once('-').unwrap_or((s, ""));
+        let [major, minor, patch] = parse_version_components::<u32, 3>(version)?;
+        let invalid = |n: u32| VersionParseError::InvalidComponent(n.to_string());
+        let major = u8::try_from(major).map_err(|_| invalid(major))?;
+        let minor = u16::try_from(minor).map_err(|_| invalid(minor))?;
+        let prerelease = Prerelease::parse(pre.as_bytes())
+            .ok_or_else(|| VersionParseError::InvalidPrerelease(pre.to_owned()))?;
+        Ok(Self::new(major, minor, patch).with_prerelease(prerelease))
+    }
+}
+
+/// Checks that the build metadata consists of non-empty dot-separated identifiers made of ASCII
+/// alphanumerics and hyphens.
+fn is_valid_build_metadata(build: &str) -> bool {
+    build
+        .split('.')
+        .all(|id| !id.is_empty() && id.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-'))
+}
+
+/// Prerelease information of a [`SemVer`]. Versions are ordered from alpha to release,
+/// and by number within the same prerelease type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Prerelease {
+    Alpha(u32),
+    Beta(u32),
+    ReleaseCandidate(u32),
+    /// A regular release.
+    None,
+}
+
+impl Prerelease {
+    const TYPE_ALPHA: u32 = 1;
+    const TYPE_BETA: u32 = 2;
+    const TYPE_NONE: u32 = 0;
+    const TYPE_RC: u32 = 3;
+
+    #[inline]
+    const fn from_raw(type_: u32, number: u32) -> Self {
+        match type_ {
+            Self::TYPE_ALPHA => Self::Alpha(number),
+            Self::TYPE_BETA => Self::Beta(number),
+            Self::TYPE_RC => Self::ReleaseCandidate(number),
+            _ => Self::None,
+        }
+    }
+
+    #[inline]
+    const fn to_raw(self) -> (u32, u32) {
+        match self {
+            Self::Alpha(n) => (Self::TYPE_ALPHA, n),
+            Self::Beta(n) => (Self::TYPE_BETA, n),
+            Self::ReleaseCandidate(n) => (Self::TYPE_RC, n),
+            Self::None => (Self::TYPE_NONE, 0),
+        }
+    }
+
+    /// Parses prerelease identifiers like `alpha`, `beta.2` or `rc1`.
+    const fn parse(bytes: &[u8]) -> Option<Self> {
+        const fn strip_prefix<'a>(bytes: &'a [u8], prefix: &[u8]) -> Option<&'a [u8]> {
+            if bytes.len() < prefix.len() {
+                return None;
+            }
+            let (head, tail) = bytes.split_at(prefix.len());
+            let mut i = 0;
+            while i < prefix.len() {
+                if head[i] != prefix[i] {
+                    return None;
+                }
+                i += 1;
+            }
+            Some(tail)
+        }
+
+        if bytes.is_empty() {
+            return Some(Self::None);
+        }
+        let (type_, rest) = if let Some(rest) = strip_prefix(bytes, b"alpha") {
+            (Self::TYPE_ALPHA, rest)
+        } else if let Some(rest) = strip_prefix(bytes, b"beta") {
+            (Self::TYPE_BETA, rest)
+        } else if let Some(rest) = strip_prefix(bytes, b"rc") {
+            (Self::TYPE_RC, rest)
+        } else {
+            return None;
+        };
+        let digits = match rest {
+            [b'.', digits @ ..] => digits,
+            _ => rest,
+        };
+        let number = match parse_decimal(digits) {
+            _ if rest.is_empty() => 0,
+            Some(n) if n <= u32::MAX as u64 => n as u32,
+            _ => return None,
+        };
+        Some(Self::from_raw(type_, number))
+    }
+}
+
+const fn parse_decimal(bytes: &[u8]) -> Option<u64> {
+    if bytes.is_empty() {
+        return None;
+    }
+    let mut value: u64 = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        if !bytes[i].is_ascii_digit() {
+            return None;
+        }
+        value = match value.checked_mul(10) {
+            Some(v) => match v.checked_add((bytes[i] - b'0') as u64) {
+                Some(v) => v,
+                None => return None,
+            },
+            None => return None,
+        };
+        i += 1;
+    }
+    Some(value)
+}
+
+/// Creates a [`SemVer`] from the version in the Cargo manifest of the calling crate.
+/// It can be used in constant contexts, like [`Plugin::VERSION`]. Compilation fails if the
+/// version cannot be represented, e.g. if the prerelease is not one of `alpha`, `beta` or `rc`.
+///
+/// # Example
+/// ```rust
+/// use red4ext_rs::{SemVer, cargo_semver};
+///
+/// const VERSION: SemVer = cargo_semver!();
+/// ```
+#[macro_export]
+macro_rules! cargo_semver {
+    () => {
+        $crate::SemVer::from_cargo_parts(
+            ::std::env!("CARGO_PKG_VERSION_MAJOR"),
+            ::std::env!("CARGO_PKG_VERSION_MINOR"),
+            ::std::env!("CARGO_PKG_VERSION_PATCH"),
+            ::std::env!("CARGO_PKG_VERSION_PRE"),
+        )
+    };
 }
 
 impl From<SemVer> for StaticArray<u16, 3> {
@@ -547,17 +1305,199 @@ impl IntoRepr for SemVer {
 }
 
 /// A version number representing the game's version.
-#[derive(Debug)]
+/// The version of the running game can be retrieved with [`SdkEnv::runtime_version`].
+///
+/// # Example
+/// ```rust
+/// use red4ext_rs::RuntimeVersion;
+///
+/// let version: RuntimeVersion = "3.0.78.22441".parse().unwrap();
+/// assert_eq!(version, RuntimeVersion::new(3, 0, 78, 22441));
+/// assert!(version > RuntimeVersion::new(3, 0, 77, 0));
+/// assert_eq!(version.to_string(), "3.0.78.22441");
+/// ```
+#[derive(Debug, Clone, Copy)]
 pub struct RuntimeVersion(red::FileVer);
 
 impl RuntimeVersion {
     /// A special version number that indicates the plugin is compatible with any game version.
-    pub const RUNTIME_INDEPENDENT: Self = Self(red::FileVer {
-        major: versioning::RUNTIME_INDEPENDENT,
-        minor: versioning::RUNTIME_INDEPENDENT,
-        build: versioning::RUNTIME_INDEPENDENT,
-        revision: versioning::RUNTIME_INDEPENDENT,
-    });
+    pub const RUNTIME_INDEPENDENT: Self = Self::new(
+        versioning::RUNTIME_INDEPENDENT,
+        versioning::RUNTIME_INDEPENDENT,
+        versioning::RUNTIME_INDEPENDENT,
+        versioning::RUNTIME_INDEPENDENT,
+    );
+
+    /// Creates a new runtime version.
+    #[inline]
+    pub const fn new(major: u16, minor: u16, build: u16, revision: u16) -> Self {
+        Self(red::FileVer {
+            major,
+            minor,
+            build,
+            revision,
+        })
+    }
+
+    #[inline]
+    pub const fn major(&self) -> u16 {
+        self.0.major
+    }
+
+    #[inline]
+    pub const fn minor(&self) -> u16 {
+        self.0.minor
+    }
+
+    #[inline]
+    pub const fn build(&self) -> u16 {
+        self.0.build
+    }
+
+    #[inline]
+    pub const fn revision(&self) -> u16 {
+        self.0.revision
+    }
+
+    /// Returns whether this is the [`RUNTIME_INDEPENDENT`](Self::RUNTIME_INDEPENDENT) version.
+    #[inline]
+    pub fn is_runtime_independent(&self) -> bool {
+        *self == Self::RUNTIME_INDEPENDENT
+    }
+
+    #[inline]
+    fn components(&self) -> [u16; 4] {
+        [self.0.major, self.0.minor, self.0.build, self.0.revision]
+    }
+}
+
+impl PartialEq for RuntimeVersion {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.components() == other.components()
+    }
+}
+
+impl Eq for RuntimeVersion {}
+
+impl PartialOrd for RuntimeVersion {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for RuntimeVersion {
+    #[inline]
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        self.components().cmp(&other.components())
+    }
+}
+
+impl hash::Hash for RuntimeVersion {
+    #[inline]
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.components().hash(state);
+    }
+}
+
+impl fmt::Display for RuntimeVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [major, minor, build, revision] = self.components();
+        write!(f, "{major}.{minor}.{build}.{revision}")
+    }
+}
+
+impl FromStr for RuntimeVersion {
+    type Err = VersionParseError;
+
+    /// Parses a version in the `major.minor.build.revision` format. All four components are
+    /// required, e.g. `2.12.0.0` rather than `2.12`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let [major, minor, build, revision] = parse_version_components(s)?;
+        Ok(Self::new(major, minor, build, revision))
+    }
+}
+
+fn parse_version_components<T, const N: usize>(s: &str) -> Result<[T; N], VersionParseError>
+where
+    T: FromStr + Default + Copy,
+{
+    let mut components = [T::default(); N];
+    let mut count = 0;
+    for part in s.trim().split('.') {
+        let slot = components
+            .get_mut(count)
+            .ok_or(VersionParseError::InvalidComponentCount(N))?;
+        *slot = part
+            .parse()
+            .map_err(|_| VersionParseError::InvalidComponent(part.to_owned()))?;
+        count += 1;
+    }
+    if count != N {
+        return Err(VersionParseError::InvalidComponentCount(N));
+    }
+    Ok(components)
+}
+
+/// A range of game versions a plugin is compatible with. The bounds are inclusive.
+/// See [`Plugin::SUPPORTED_RUNTIMES`] for more information.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RuntimeVersionRange {
+    min: RuntimeVersion,
+    max: Option<RuntimeVersion>,
+}
+
+impl RuntimeVersionRange {
+    /// Creates a range including all versions between `min` and `max`.
+    #[inline]
+    pub const fn between(min: RuntimeVersion, max: RuntimeVersion) -> Self {
+        Self {
+            min,
+            max: Some(max),
+        }
+    }
+
+    /// Creates a range including `min` and all later versions.
+    #[inline]
+    pub const fn at_least(min: RuntimeVersion) -> Self {
+        Self { min, max: None }
+    }
+
+    /// Creates a range including only the specified version.
+    #[inline]
+    pub const fn exact(version: RuntimeVersion) -> Self {
+        Self::between(version, version)
+    }
+
+    /// Returns whether the range includes the specified version.
+    #[inline]
+    pub fn contains(&self, version: &RuntimeVersion) -> bool {
+        self.min <= *version && self.max.is_none_or(|max| *version <= max)
+    }
+}
+
+impl fmt::Display for RuntimeVersionRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.max {
+            Some(max) if max == self.min => write!(f, "{max}"),
+            Some(max) => write!(f, "{} to {max}", self.min),
+            None => write!(f, "{} or later", self.min),
+        }
+    }
+}
+
+/// An error returned when parsing a version number fails.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum VersionParseError {
+    #[error("the version should have exactly {0} components")]
+    InvalidComponentCount(usize),
+    #[error("invalid version component '{0}'")]
+    InvalidComponent(String),
+    #[error("invalid prerelease '{0}', expected alpha, beta or rc followed by a number")]
+    InvalidPrerelease(String),
+    #[error("invalid build metadata '{0}'")]
+    InvalidBuildMetadata(String),
 }
 
 /// A version number representing the RED4ext SDK version.
@@ -593,24 +1533,108 @@ impl From<ApiVersion> for u32 {
 /// The hooks are defined as static variables and must be initialized with a call to
 /// [`SdkEnv::attach_hook`].
 ///
+/// A hook can optionally declare its target, either as an [`addr_hashes`] constant or as the
+/// full name of an RTTI native function. Such hooks can be attached with
+/// [`SdkEnv::attach_hook_resolved`], which resolves the target address automatically.
+///
+/// Functions returning `#[repr(C)]` structs by value are supported as long as they follow the
+/// C calling convention. C++ member functions returning structs use a different convention,
+/// where the hidden pointer to the return value is passed right after `this`. Such functions
+/// can be declared with `-> sret T`, the first argument is then treated as `this`. The detour
+/// still receives and returns the value of `T` directly, the return pointer is handled by the
+/// hook.
+///
+/// Hooked functions can accept up to 17 arguments, the detour accepts one more for the
+/// callback to the original function.
+///
+/// Detours are `extern "C"` functions, so a panic escaping a detour aborts the process. Detours
+/// that can panic should catch the panic themselves, e.g. with [`std::panic::catch_unwind`].
+///
 /// # Example
 /// ```rust
-/// use red4ext_rs::hooks;
+/// use red4ext_rs::types::{IScriptable, StackFrame, RedString};
+/// use red4ext_rs::{VoidPtr, addr_hashes, hooks};
 ///
 /// hooks! {
 ///    static ADD_HOOK: fn(a: u32, b: u32) -> u32;
+///    static EXECUTE_HOOK: fn(f: VoidPtr, s: VoidPtr) -> bool = addr_hashes::CBaseFunction_ExecuteNative;
+///    static ON_DEATH_HOOK: fn(
+///        i: *mut IScriptable,
+///        f: *mut StackFrame,
+///        a3: VoidPtr,
+///        a4: VoidPtr
+///    ) -> () = "PlayerPuppet::OnDeath";
+///    static TO_STRING_HOOK: fn(this: *mut IScriptable) -> sret RedString;
 /// }
 /// ```
 #[macro_export]
 macro_rules! hooks {
-    ($(static $name:ident: fn($($arg:ident: $ty:ty),*) -> $ret:ty;)*) => {$(
+    () => {};
+    (@target) => { None };
+    (@target $target:expr) => {{
+        fn target() -> $crate::HookTarget {
+            ::std::convert::From::from($target)
+        }
+        Some(target as fn() -> $crate::HookTarget)
+    }};
+    (
+        static $name:ident: fn($this:ident: $this_ty:ty $(, $arg:ident: $ty:ty)*) -> sret $ret:ty $(= $target:expr)?;
+        $($rest:tt)*
+    ) => {
+        static mut $name: *mut $crate::Hook<
+            unsafe extern "C" fn($this: $this_ty, out: *mut $ret $(, $arg: $ty)*) -> *mut $ret,
+            unsafe extern "C" fn($this: $this_ty, $($arg: $ty,)* cb: unsafe extern "C" fn($this: $this_ty $(, $arg: $ty)*) -> $ret) -> $ret
+        > = unsafe {
+
+            static mut TARGET: Option<unsafe extern "C" fn($this: $this_ty, out: *mut $ret $(, $arg: $ty)*) -> *mut $ret> = None;
+            static mut DETOUR: Option<unsafe extern "C" fn($this: $this_ty, $($arg: $ty,)* cb: unsafe extern "C" fn($this: $this_ty $(, $arg: $ty)*) -> $ret) -> $ret> = None;
+
+            unsafe extern "C" fn original($this: $this_ty $(, $arg: $ty)*) -> $ret {
+                unsafe {
+                    let target = TARGET.expect("target function should be set");
+                    let mut out = ::std::mem::MaybeUninit::<$ret>::uninit();
+                    target($this, out.as_mut_ptr() $(, $arg)*);
+                    out.assume_init()
+                }
+            }
+
+            unsafe extern "C" fn internal($this: $this_ty, out: *mut $ret $(, $arg: $ty)*) -> *mut $ret {
+                unsafe {
+                    let detour = DETOUR.expect("detour function should be set");
+                    out.write(detour($this, $($arg,)* original));
+                    out
+                }
+            }
+
+            static mut HOOK: $crate::Hook<
+                unsafe extern "C" fn($this: $this_ty, out: *mut $ret $(, $arg: $ty)*) -> *mut $ret,
+                unsafe extern "C" fn($this: $this_ty, $($arg: $ty,)* cb: unsafe extern "C" fn($this: $this_ty $(, $arg: $ty)*) -> $ret) -> $ret
+            > = unsafe {
+                $crate::Hook::new(
+                    stringify!($name),
+                    internal,
+                    ::std::ptr::addr_of_mut!(TARGET),
+                    ::std::ptr::addr_of_mut!(DETOUR),
+                    $crate::hooks!(@target $($target)?),
+                )
+            };
+
+            ::std::ptr::addr_of_mut!(HOOK)
+        };
+
+        $crate::hooks!($($rest)*);
+    };
+    (
+        static $name:ident: fn($($arg:ident: $ty:ty),*) -> $ret:ty $(= $target:expr)?;
+        $($rest:tt)*
+    ) => {
         static mut $name: *mut $crate::Hook<
             unsafe extern "C" fn($($arg: $ty),*) -> $ret,
-            unsafe extern "C" fn($($arg: $ty),*, cb: unsafe extern "C" fn($($arg: $ty),*) -> $ret) -> $ret
+            unsafe extern "C" fn($($arg: $ty,)* cb: unsafe extern "C" fn($($arg: $ty),*) -> $ret) -> $ret
         > = unsafe {
 
             static mut TARGET: Option<unsafe extern "C" fn($($arg: $ty),*) -> $ret> = None;
-            static mut DETOUR: Option<unsafe extern "C" fn($($arg: $ty),*, cb: unsafe extern "C" fn($($arg: $ty),*) -> $ret) -> $ret> = None;
+            static mut DETOUR: Option<unsafe extern "C" fn($($arg: $ty,)* cb: unsafe extern "C" fn($($arg: $ty),*) -> $ret) -> $ret> = None;
 
             unsafe extern "C" fn internal($($arg: $ty),*) -> $ret {
                 unsafe {
@@ -622,24 +1646,46 @@ macro_rules! hooks {
 
             static mut HOOK: $crate::Hook<
                 unsafe extern "C" fn($($arg: $ty),*) -> $ret,
-                unsafe extern "C" fn($($arg: $ty),*, cb: unsafe extern "C" fn($($arg: $ty),*) -> $ret) -> $ret
-            > = unsafe { $crate::Hook::new(internal, ::std::ptr::addr_of_mut!(TARGET), ::std::ptr::addr_of_mut!(DETOUR)) };
+                unsafe extern "C" fn($($arg: $ty,)* cb: unsafe extern "C" fn($($arg: $ty),*) -> $ret) -> $ret
+            > = unsafe {
+                $crate::Hook::new(
+                    stringify!($name),
+                    internal,
+                    ::std::ptr::addr_of_mut!(TARGET),
+                    ::std::ptr::addr_of_mut!(DETOUR),
+                    $crate::hooks!(@target $($target)?),
+                )
+            };
 
             ::std::ptr::addr_of_mut!(HOOK)
         };
-    )*};
+
+        $crate::hooks!($($rest)*);
+    };
 }
 
 /// A wrapper around function pointers that can be passed to [`SdkEnv::attach_hook`] to install
 /// detours.
 #[derive(Debug)]
-pub struct Hook<O, R>(O, *mut Option<O>, *mut Option<R>);
+pub struct Hook<O, R>(
+    O,
+    *mut Option<O>,
+    *mut Option<R>,
+    &'static str,
+    Option<fn() -> HookTarget>,
+);
 
 #[doc(hidden)]
 impl<O, R> Hook<O, R> {
     #[inline]
-    pub const fn new(original: O, cb_ref: *mut Option<O>, detour_ref: *mut Option<R>) -> Self {
-        Self(original, cb_ref, detour_ref)
+    pub const fn new(
+        name: &'static str,
+        original: O,
+        cb_ref: *mut Option<O>,
+        detour_ref: *mut Option<R>,
+        target: Option<fn() -> HookTarget>,
+    ) -> Self {
+        Self(original, cb_ref, detour_ref, name, target)
     }
 }
 
@@ -647,17 +1693,33 @@ impl<O, R> Hook<O, R> {
 /// satisfy this requirement.
 #[sealed]
 pub trait FnPtr<Args, Ret> {
+    /// The number of arguments accepted by the function.
+    const ARITY: usize;
+
     fn to_ptr(&self) -> VoidPtr;
+
+    /// Converts a pointer back to a function.
+    ///
+    /// # Safety
+    /// The pointer must point to a function with a compatible signature.
+    unsafe fn from_ptr(ptr: VoidPtr) -> Self;
 }
 
 macro_rules! impl_fn_ptr {
     ($($ty:ident),*) => {
         #[sealed]
         impl <$($ty,)* Ret> FnPtr<($($ty,)*), Ret> for unsafe extern "C" fn($($ty,)*) -> Ret {
+            const ARITY: usize = <[&str]>::len(&[$(stringify!($ty)),*]);
+
             #[inline]
             fn to_ptr(&self) -> VoidPtr {
                 *self as _
             }
+
+            #[inline]
+            unsafe fn from_ptr(ptr: VoidPtr) -> Self {
+                unsafe { mem::transmute::<VoidPtr, Self>(ptr) }
+            }
         }
     }
 }
@@ -673,6 +1735,14 @@ impl_fn_ptr!(A, B, C, D, E, F, G);
 impl_fn_ptr!(A, B, C, D, E, F, G, H);
 impl_fn_ptr!(A, B, C, D, E, F, G, H, I);
 impl_fn_ptr!(A, B, C, D, E, F, G, H, I, J);
+impl_fn_ptr!(A, B, C, D, E, F, G, H, I, J, K);
+impl_fn_ptr!(A, B, C, D, E, F, G, H, I, J, K, L);
+impl_fn_ptr!(A, B, C, D, E, F, G, H, I, J, K, L, M);
+impl_fn_ptr!(A, B, C, D, E, F, G, H, I, J, K, L, M, N);
+impl_fn_ptr!(A, B, C, D, E, F, G, H, I, J, K, L, M, N, O);
+impl_fn_ptr!(A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P);
+impl_fn_ptr!(A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q);
+impl_fn_ptr!(A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R);
 
 /// A callback function to be called when a state is entered, updated, or exited.
 pub type StateHandler = unsafe extern "C" fn(app: &GameApp) -> StateHandlerResult;
@@ -729,7 +1799,7 @@ impl StateListener {
 }
 
 /// An enum representing different types of game states.
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u32)]
 pub enum StateType {
     BaseInitialization = red::EGameStateType::BaseInitialization,
@@ -805,6 +1875,13 @@ const fn fnv1a32(str: &str) -> u32 {
     }
 }
 
+/// Locks a mutex, ignoring poisoning. The data guarded by the crate's locks is never left in an
+/// inconsistent state by a panicking callback.
+#[inline]
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
 fn truncated_cstring(mut s: String) -> ffi::CString {
     s.truncate(s.find('\0').unwrap_or(s.len()));
     unsafe { CString::from_vec_unchecked(s.into_bytes()) }
@@ -834,15 +1911,166 @@ macro_rules! fn_from_hash {
 
 pub(crate) use fn_from_hash;
 
-#[cold]
-#[track_caller]
-fn check_invariant(success: bool, message: &'static str) {
-    #[cfg(feature = "log")]
-    if !success {
-        log::error!(
-            "invariant violated: {message}: {}",
-            std::panic::Location::caller(),
+#[cfg(test)]
+mod tests {
+    use std::mem::MaybeUninit;
+
+    use super::{
+        Hook, HookTarget, Prerelease, RuntimeVersion, RuntimeVersionRange, SemVer,
+        VersionParseError,
+    };
+
+    #[repr(C)]
+    #[derive(Debug, PartialEq)]
+    struct Pair(u32, u32);
+
+    hooks! {
+        static SRET_HOOK: fn(this: *const u32, a: u32) -> sret Pair = 0x1234u32;
+    }
+
+    unsafe extern "C" fn sret_target(this: *const u32, out: *mut Pair, a: u32) -> *mut Pair {
+        unsafe { out.write(Pair(*this, a)) };
+        out
+    }
+
+    unsafe extern "C" fn sret_detour(
+        this: *const u32,
+        a: u32,
+        cb: unsafe extern "C" fn(*const u32, u32) -> Pair,
+    ) -> Pair {
+        let Pair(x, y) = unsafe { cb(this, a * 2) };
+        Pair(x + 1, y)
+    }
+
+    #[test]
+    fn parse_runtime_version() {
+        assert_eq!(
+            "2.12.0.1".parse::<RuntimeVersion>(),
+            Ok(RuntimeVersion::new(2, 12, 0, 1))
+        );
+        assert_eq!(
+            "2.12".parse::<RuntimeVersion>(),
+            Err(VersionParseError::InvalidComponentCount(4))
+        );
+        assert_eq!(
+            "2.12.0.1.5".parse::<RuntimeVersion>(),
+            Err(VersionParseError::InvalidComponentCount(4))
+        );
+        assert_eq!(
+            "2.x".parse::<RuntimeVersion>(),
+            Err(VersionParseError::InvalidComponent("x".to_owned()))
+        );
+    }
+
+    #[test]
+    fn runtime_version_range() {
+        let range = RuntimeVersionRange::between(
+            RuntimeVersion::new(2, 10, 0, 0),
+            RuntimeVersion::new(2, 12, 0, 0),
+        );
+        assert!(range.contains(&RuntimeVersion::new(2, 10, 0, 0)));
+        assert!(range.contains(&RuntimeVersion::new(2, 11, 5, 3)));
+        assert!(range.contains(&RuntimeVersion::new(2, 12, 0, 0)));
+        assert!(!range.contains(&RuntimeVersion::new(2, 12, 0, 1)));
+        assert!(!range.contains(&RuntimeVersion::new(1, 63, 0, 0)));
+
+        let range = RuntimeVersionRange::at_least(RuntimeVersion::new(2, 10, 0, 0));
+        assert!(range.contains(&RuntimeVersion::new(3, 0, 0, 0)));
+        assert!(!range.contains(&RuntimeVersion::new(2, 9, 0, 0)));
+    }
+
+    #[test]
+    fn parse_semver() {
+        assert_eq!("1.2.3".parse::<SemVer>(), Ok(SemVer::new(1, 2, 3)));
+        assert_eq!(
+            "1.2.3-rc.2".parse::<SemVer>(),
+            Ok(SemVer::new(1, 2, 3).with_prerelease(Prerelease::ReleaseCandidate(2)))
+        );
+        assert_eq!(
+            "1.2.3-alpha".parse::<SemVer>(),
+            Ok(SemVer::new(1, 2, 3).with_prerelease(Prerelease::Alpha(0)))
+        );
+        assert_eq!(
+            "1.2.3-dev".parse::<SemVer>(),
+            Err(VersionParseError::InvalidPrerelease("dev".to_owned()))
+        );
+        assert_eq!(
+            "256.0.0".parse::<SemVer>(),
+            Err(VersionParseError::InvalidComponent("256".to_owned()))
+        );
+        assert_eq!(
+            "1.2".parse::<SemVer>(),
+            Err(VersionParseError::InvalidComponentCount(3))
+        );
+        assert_eq!(
+            "1".parse::<SemVer>(),
+            Err(VersionParseError::InvalidComponentCount(3))
+        );
+    }
+
+    #[test]
+    fn parse_semver_build_metadata() {
+        assert_eq!("1.2.3+build.5".parse::<SemVer>(), Ok(SemVer::new(1, 2, 3)));
+        assert_eq!(
+            "1.2.3-rc.1+sha-5114f85".parse::<SemVer>(),
+            Ok(SemVer::new(1, 2, 3).with_prerelease(Prerelease::ReleaseCandidate(1)))
+        );
+        assert_eq!(
+            "1.2.3+".parse::<SemVer>(),
+            Err(VersionParseError::InvalidBuildMetadata(String::new()))
+        );
+        assert_eq!(
+            "1.2.3+build..5".parse::<SemVer>(),
+            Err(VersionParseError::InvalidBuildMetadata(
+                "build..5".to_owned()
+            ))
         );
     }
-    assert!(success, "{message}");
+
+    #[test]
+    fn semver_ordering() {
+        let release = SemVer::new(1, 0, 0);
+        let rc = release.with_prerelease(Prerelease::ReleaseCandidate(1));
+        let beta = release.with_prerelease(Prerelease::Beta(2));
+        assert!(beta < rc);
+        assert!(rc < release);
+        assert!(release < SemVer::new(1, 0, 1));
+        assert_eq!(rc.to_string(), "1.0.0-rc.1");
+    }
+
+    #[test]
+    fn semver_from_cargo() {
+        const VERSION: SemVer = SemVer::from_cargo_parts("0", "10", "2", "beta.1");
+        assert_eq!(
+            VERSION,
+            SemVer::new(0, 10, 2).with_prerelease(Prerelease::Beta(1))
+        );
+    }
+
+    #[test]
+    fn sret_hook_declaration() {
+        let hook = unsafe { &*SRET_HOOK };
+        assert_eq!(hook.3, "SRET_HOOK");
+        assert_eq!(
+            hook.4.map(|target| target()),
+            Some(HookTarget::Hash(0x1234))
+        );
+    }
+
+    #[test]
+    fn sret_hook_forwards_return_pointer() {
+        let Hook(internal, target, detour, ..) = unsafe { &*SRET_HOOK };
+        unsafe {
+            target.write(Some(sret_target));
+            detour.write(Some(sret_detour));
+        }
+
+        let this = 10u32;
+        let mut out = MaybeUninit::<Pair>::uninit();
+        let ret = unsafe { (*internal)(&this, out.as_mut_ptr(), 4) };
+        // the detour receives the value produced by the target through the hidden pointer, and
+        // its own result is written to the pointer provided by the caller
+        assert_eq!(ret, out.as_mut_ptr());
+        assert_eq!(unsafe { out.assume_init() }, Pair(11, 8));
+    }
 }
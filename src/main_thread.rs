@@ -0,0 +1,319 @@
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex, OnceLock, PoisonError};
+use std::task::{Context, Poll, Waker};
+use std::thread::{self, ThreadId};
+use std::time::{Duration, Instant};
+use std::{fmt, mem};
+
+use thiserror::Error;
+
+use crate::unwind::catch_unwind;
+use crate::{ClosureListener, SdkEnv, StateHandlerResult, StateType, lock};
+
+type Job = Box<dyn FnOnce() + Send>;
+
+static QUEUE: Mutex<Queue> = Mutex::new(Queue {
+    jobs: VecDeque::new(),
+    state: QueueState::Pending,
+});
+static MAIN_THREAD: OnceLock<ThreadId> = OnceLock::new();
+
+/// A queue of work to be executed on the game thread. Functions can be posted from any thread,
+/// and they are executed in order during the update callback of the
+/// [`Running`](StateType::Running) state.
+///
+/// Calling game functions or interacting with RTTI objects from background threads is not safe,
+/// this type provides a way to hand such work over to the game thread.
+///
+/// The queue is closed once the running state is exited or the plugin is unloaded. Functions
+/// that are still pending at that point are dropped without being executed, and so are the
+/// functions posted afterwards.
+///
+/// # Example
+/// ```rust
+/// use std::thread;
+///
+/// use red4ext_rs::{MainThread, call};
+///
+/// fn compute_in_background() {
+///     thread::spawn(|| {
+///         let input = 2i32;
+///         // blocks until the game thread has executed the function
+///         let result = MainThread::post_and_wait(move || {
+///             call!("OperatorAdd;Int32Int32;Int32" (input, 2i32) -> i32).unwrap()
+///         });
+///         assert_eq!(result.unwrap(), 4);
+///     });
+/// }
+/// ```
+#[derive(Debug)]
+pub struct MainThread;
+
+impl MainThread {
+    /// Posts a function to be executed on the game thread during the next update.
+    /// The function is dropped without being executed if the queue has been closed.
+    #[inline]
+    pub fn post<F>(f: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let mut queue = lock(&QUEUE);
+        if queue.state != QueueState::Closed {
+            queue.jobs.push_back(Box::new(f));
+        }
+    }
+
+    /// Posts a function to be executed on the game thread and blocks the current thread until
+    /// its result is available. If called from the game thread, the function is executed
+    /// immediately to avoid a deadlock.
+    ///
+    /// Posted functions are only executed while the game is in the running state. This returns
+    /// [`MainThreadError::NotRunning`] if the game isn't running yet or anymore, and
+    /// [`MainThreadError::Cancelled`] if the game stops running before the function is executed.
+    /// If the game thread blocks on the calling thread in the meantime, for instance by joining
+    /// it, neither can make progress and the game deadlocks. Use
+    /// [`post_and_wait_timeout`](Self::post_and_wait_timeout) to put a bound on the wait.
+    pub fn post_and_wait<F, R>(f: F) -> Result<R, MainThreadError>
+    where
+        F: FnOnce() -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        if Self::is_current() {
+            return Ok(f());
+        }
+        Self::post_running(f)?.wait()
+    }
+
+    /// Like [`post_and_wait`](Self::post_and_wait), but gives up after `timeout` has passed,
+    /// returning [`MainThreadError::TimedOut`]. The function is not cancelled when the wait
+    /// times out, it might still be executed later.
+    pub fn post_and_wait_timeout<F, R>(f: F, timeout: Duration) -> Result<R, MainThreadError>
+    where
+        F: FnOnce() -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        if Self::is_current() {
+            return Ok(f());
+        }
+        Self::post_running(f)?.wait_timeout(timeout)
+    }
+
+    /// Posts a function to be executed on the game thread and returns a [`MainThreadTask`]
+    /// which can be awaited or waited on to retrieve its result.
+    pub fn post_async<F, R>(f: F) -> MainThreadTask<R>
+    where
+        F: FnOnce() -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let shared = Arc::new(Shared::default());
+        let sender = Sender(shared.clone());
+        Self::post(move || sender.send(f()));
+        MainThreadTask(shared)
+    }
+
+    fn post_running<F, R>(f: F) -> Result<MainThreadTask<R>, MainThreadError>
+    where
+        F: FnOnce() -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        if lock(&QUEUE).state != QueueState::Open {
+            return Err(MainThreadError::NotRunning);
+        }
+        Ok(Self::post_async(f))
+    }
+
+    /// Returns whether the current thread is the game thread.
+    #[inline]
+    pub fn is_current() -> bool {
+        MAIN_THREAD.get() == Some(&thread::current().id())
+    }
+
+    /// Registers the calling thread as the game thread and attaches the queue to the callbacks
+    /// of the running state. Called while the plugin is being loaded.
+    pub(crate) fn attach(env: &SdkEnv) {
+        MAIN_THREAD.get_or_init(|| thread::current().id());
+
+        let listener = ClosureListener::default()
+            .with_on_enter(|_| {
+                let mut queue = lock(&QUEUE);
+                if queue.state == QueueState::Pending {
+                    queue.state = QueueState::Open;
+                }
+                StateHandlerResult::Finished
+            })
+            .with_on_update(|_| {
+                Self::drain();
+                StateHandlerResult::Finished
+            })
+            .with_on_exit(|_| {
+                Self::close();
+                StateHandlerResult::Finished
+            });
+        env.add_closure_listener(StateType::Running, listener);
+    }
+
+    /// Closes the queue and drops the pending functions, which cancels their tasks.
+    pub(crate) fn close() {
+        let jobs = {
+            let mut queue = lock(&QUEUE);
+            queue.state = QueueState::Closed;
+            mem::take(&mut queue.jobs)
+        };
+        // the jobs are dropped after the lock is released, their destructors notify the waiters
+        drop(jobs);
+    }
+
+    fn drain() {
+        // functions posted while draining are deferred to the next update
+        let jobs = mem::take(&mut lock(&QUEUE).jobs);
+        for job in jobs {
+            catch_unwind("main thread job", job);
+        }
+    }
+}
+
+/// An error returned when a function posted to the game thread did not produce a result.
+#[derive(Debug, Error)]
+pub enum MainThreadError {
+    #[error("the function was dropped before it could complete")]
+    Cancelled,
+    #[error("the game is not running")]
+    NotRunning,
+    #[error("timed out waiting for the function to complete")]
+    TimedOut,
+}
+
+/// A handle to the result of a function posted with [`MainThread::post_async`].
+/// It can be awaited, or waited on synchronously with [`MainThreadTask::wait`].
+pub struct MainThreadTask<R>(Arc<Shared<R>>);
+
+impl<R> MainThreadTask<R> {
+    /// Blocks the current thread until the result is available.
+    /// This must not be called on the game thread, since it would never complete.
+    /// Returns [`MainThreadError::Cancelled`] if the function is dropped without being executed,
+    /// which happens when the game stops running or the plugin is unloaded.
+    pub fn wait(self) -> Result<R, MainThreadError> {
+        let mut slot = lock(&self.0.slot);
+        loop {
+            if let Some(value) = slot.value.take() {
+                return Ok(value);
+            }
+            if slot.closed {
+                return Err(MainThreadError::Cancelled);
+            }
+            slot = self
+                .0
+                .ready
+                .wait(slot)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    /// Blocks the current thread until the result is available or `timeout` has passed, in
+    /// which case [`MainThreadError::TimedOut`] is returned.
+    pub fn wait_timeout(self, timeout: Duration) -> Result<R, MainThreadError> {
+        let deadline = Instant::now() + timeout;
+        let mut slot = lock(&self.0.slot);
+        loop {
+            if let Some(value) = slot.value.take() {
+                return Ok(value);
+            }
+            if slot.closed {
+                return Err(MainThreadError::Cancelled);
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(MainThreadError::TimedOut);
+            }
+            slot = self
+                .0
+                .ready
+                .wait_timeout(slot, remaining)
+                .unwrap_or_else(PoisonError::into_inner)
+                .0;
+        }
+    }
+}
+
+impl<R> Future for MainThreadTask<R> {
+    type Output = Result<R, MainThreadError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut slot = lock(&self.0.slot);
+        if let Some(value) = slot.value.take() {
+            return Poll::Ready(Ok(value));
+        }
+        if slot.closed {
+            return Poll::Ready(Err(MainThreadError::Cancelled));
+        }
+        slot.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl<R> fmt::Debug for MainThreadTask<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MainThreadTask").finish_non_exhaustive()
+    }
+}
+
+struct Queue {
+    jobs: VecDeque<Job>,
+    state: QueueState,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum QueueState {
+    Pending,
+    Open,
+    Closed,
+}
+
+struct Shared<R> {
+    slot: Mutex<Slot<R>>,
+    ready: Condvar,
+}
+
+impl<R> Default for Shared<R> {
+    fn default() -> Self {
+        Self {
+            slot: Mutex::new(Slot {
+                value: None,
+                closed: false,
+                waker: None,
+            }),
+            ready: Condvar::new(),
+        }
+    }
+}
+
+struct Slot<R> {
+    value: Option<R>,
+    closed: bool,
+    waker: Option<Waker>,
+}
+
+struct Sender<R>(Arc<Shared<R>>);
+
+impl<R> Sender<R> {
+    fn send(self, value: R) {
+        lock(&self.0.slot).value = Some(value);
+        // the receiver is notified when the sender is dropped
+    }
+}
+
+impl<R> Drop for Sender<R> {
+    fn drop(&mut self) {
+        let waker = {
+            let mut slot = lock(&self.0.slot);
+            slot.closed = true;
+            slot.waker.take()
+        };
+        self.0.ready.notify_all();
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
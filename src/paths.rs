@@ -0,0 +1,52 @@
+#[cfg(windows)]
+use std::ffi::OsString;
+use std::io;
+#[cfg(windows)]
+use std::os::windows::ffi::OsStringExt;
+use std::path::PathBuf;
+
+use crate::VoidPtr;
+
+/// The initial size of the buffer used to retrieve module paths, in UTF-16 code units.
+const MAX_PATH: usize = 260;
+
+/// The location of the game executable relative to the game root, `bin\x64\Cyberpunk2077.exe`.
+const EXECUTABLE_DEPTH: usize = 3;
+
+#[cfg(windows)]
+unsafe extern "system" {
+    fn GetModuleFileNameW(module: VoidPtr, filename: *mut u16, size: u32) -> u32;
+}
+
+/// Returns the full path of a module. A null module refers to the executable of the process.
+#[cfg(windows)]
+pub(crate) fn module_path(module: VoidPtr) -> io::Result<PathBuf> {
+    let mut buf = vec![0u16; MAX_PATH];
+    loop {
+        let len = unsafe { GetModuleFileNameW(module, buf.as_mut_ptr(), buf.len() as u32) };
+        match len as usize {
+            0 => return Err(io::Error::last_os_error()),
+            // the path has been truncated
+            len if len == buf.len() => buf.resize(buf.len() * 2, 0),
+            len => return Ok(OsString::from_wide(&buf[..len]).into()),
+        }
+    }
+}
+
+/// Module paths can only be retrieved on Windows, this allows building docs and tests elsewhere.
+#[cfg(not(windows))]
+pub(crate) fn module_path(_module: VoidPtr) -> io::Result<PathBuf> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "module paths are only available on Windows",
+    ))
+}
+
+/// Returns the root directory of the game installation.
+pub(crate) fn game_dir() -> io::Result<PathBuf> {
+    let exe = module_path(std::ptr::null_mut())?;
+    exe.ancestors()
+        .nth(EXECUTABLE_DEPTH)
+        .map(PathBuf::from)
+        .ok_or_else(|| io::Error::other("unexpected location of the game executable"))
+}
@@ -1,9 +1,12 @@
+use std::fmt;
+
 use const_combine::bounded::const_combine as combine;
 
+use crate::ENV;
 use crate::class::ScriptClass;
 use crate::types::{
-    CName, EntityId, GameTime, ItemId, Opt, RedArray, RedString, Ref, ScriptRef, TweakDbId,
-    Variant, WeakRef,
+    CName, EngineTime, EntityId, GameTime, ItemId, Opt, RedArray, RedString, Ref, ScriptRef,
+    TweakDbId, Variant, WeakRef,
 };
 
 /// A trait for types that can be passed across the FFI boundary to the game engine without
@@ -17,6 +20,120 @@ pub unsafe trait NativeRepr {
     const NAME: &'static str;
 }
 
+/// A trait for fieldless Rust enums that can be exported to the game with
+/// [`EnumExport`](crate::EnumExport). It's usually implemented with the
+/// [`native_enum!`](crate::native_enum) macro.
+///
+/// Enums are passed across the FFI boundary as their raw integer values wrapped in
+/// [`EnumRepr`], since the game can pass values that don't match any of the variants.
+///
+/// # Safety
+///
+/// [`Raw`](Self::Raw) must be a primitive integer type, and [`VARIANTS`](Self::VARIANTS) must
+/// list all of the variants along with their discriminants.
+pub unsafe trait NativeEnum: Sized + 'static {
+    /// The name of the enum in the game.
+    const NAME: &'static str;
+    /// The names and values of the variants.
+    const VARIANTS: &'static [(&'static str, i64)];
+
+    /// The integer representation of the enum.
+    type Raw: Copy + Default + fmt::Debug;
+
+    /// Converts a raw value into a variant.
+    /// Returns [`None`] if the value doesn't match any of the variants.
+    fn from_raw(raw: Self::Raw) -> Option<Self>;
+
+    /// Converts the variant into its raw value.
+    fn into_raw(self) -> Self::Raw;
+}
+
+/// The representation of an enum implementing [`NativeEnum`] passed across the FFI boundary.
+/// It holds the raw value of the enum, which might not match any of its variants.
+#[derive(Debug)]
+#[repr(transparent)]
+pub struct EnumRepr<E: NativeEnum>(E::Raw);
+
+impl<E: NativeEnum> EnumRepr<E> {
+    /// Creates a representation from a raw value.
+    #[inline]
+    pub const fn new(raw: E::Raw) -> Self {
+        Self(raw)
+    }
+
+    /// Returns the raw value.
+    #[inline]
+    pub fn raw(&self) -> E::Raw {
+        self.0
+    }
+
+    /// Converts the raw value into a variant.
+    /// Returns [`None`] if the value doesn't match any of the variants.
+    #[inline]
+    pub fn get(&self) -> Option<E> {
+        E::from_raw(self.0)
+    }
+
+    /// Converts the raw value into a variant.
+    /// If the value doesn't match any of the variants, it's logged and the default variant is
+    /// returned.
+    pub fn get_or_default(&self) -> E
+    where
+        E: Default,
+    {
+        self.get().unwrap_or_else(|| {
+            if let Some(env) = ENV.get() {
+                env.warn(format_args!(
+                    "unexpected value {:?} of enum {}, using the default variant",
+                    self.0,
+                    E::NAME
+                ));
+            }
+            E::default()
+        })
+    }
+}
+
+impl<E: NativeEnum> From<E> for EnumRepr<E> {
+    #[inline]
+    fn from(value: E) -> Self {
+        Self(value.into_raw())
+    }
+}
+
+impl<E: NativeEnum> Clone for EnumRepr<E> {
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<E: NativeEnum> Copy for EnumRepr<E> {}
+
+impl<E: NativeEnum + Default> Default for EnumRepr<E> {
+    #[inline]
+    fn default() -> Self {
+        Self::from(E::default())
+    }
+}
+
+unsafe impl<E: NativeEnum> NativeRepr for EnumRepr<E> {
+    const NAME: &'static str = E::NAME;
+}
+
+/// A trait for flag sets that can be exported to the game as bitfields with
+/// [`BitfieldExport`](crate::BitfieldExport). It's usually implemented with the
+/// [`native_bitfield!`](crate::native_bitfield) macro.
+///
+/// # Safety
+///
+/// The type must have the representation of an unsigned integer, and each flag must be
+/// represented by the bit listed in [`BITS`](Self::BITS).
+pub unsafe trait NativeBitfield: NativeRepr {
+    /// The names and positions of the bits.
+    const BITS: &'static [(&'static str, u8)];
+}
+
 unsafe impl NativeRepr for () {
     const NAME: &'static str = "Void";
 }
@@ -70,6 +187,7 @@ impl_native_repr!(TweakDbId, "TweakDBID");
 impl_native_repr!(ItemId, "ItemID", "gameItemID");
 impl_native_repr!(EntityId, "EntityID", "entEntityID");
 impl_native_repr!(GameTime, "GameTime", "GameTime");
+impl_native_repr!(EngineTime, "EngineTime");
 impl_native_repr!(Variant, "Variant", "Variant");
 
 /// A trait for types that can be converted into a representation that can be passed across
@@ -0,0 +1,387 @@
+use std::num::NonZeroU32;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, Once};
+use std::time::Duration;
+use std::{fmt, mem};
+
+use crate::types::{EngineTime, EngineTimeError, GameInstance, Ref, WeakRef};
+use crate::unwind::catch_unwind;
+use crate::{ClosureListener, ENV, ScriptClass, SdkEnv, StateHandlerResult, StateType, call, lock};
+
+type BoxedTask = Box<dyn FnMut() -> TaskStatus + Send>;
+
+/// A frame-based task scheduler driven by the update callback of the
+/// [`Running`](StateType::Running) state. It can be obtained using [`SdkEnv::scheduler`].
+///
+/// The scheduler keeps its own clock, which starts at zero and advances by the simulation time
+/// of the game elapsed between consecutive frames. The simulation time doesn't advance while the
+/// game is paused, so neither do the delays of the scheduled tasks. Tasks are only executed while
+/// the game is running.
+///
+/// # Example
+/// ```rust
+/// use std::time::Duration;
+///
+/// use red4ext_rs::{Schedule, SdkEnv};
+///
+/// fn schedule_tasks(env: &SdkEnv) {
+///     env.scheduler().schedule(Schedule::NextFrame, || {
+///         // do something on the next frame...
+///     });
+///     let handle =
+///         env.scheduler()
+///             .schedule(Schedule::every(Duration::from_secs(1)).unwrap(), || {
+///                 // do something every second...
+///             });
+///     // the repeating task can be stopped at any point
+///     handle.cancel();
+/// }
+/// ```
+pub struct Scheduler {
+    tasks: Mutex<Vec<Task>>,
+    clock: Mutex<Clock>,
+    attached: Once,
+}
+
+impl Scheduler {
+    /// Schedules a task to be executed according to `when`.
+    /// Returns a handle that can be used to cancel the task.
+    pub fn schedule<F>(&self, when: Schedule, mut task: F) -> TaskHandle
+    where
+        F: FnMut() + Send + 'static,
+    {
+        self.push(
+            when,
+            Box::new(move || {
+                task();
+                TaskStatus::Alive
+            }),
+        )
+    }
+
+    /// Schedules a task bound to the lifetime of a game object. The task receives a strong
+    /// reference to the object each time it's executed, and it's cancelled automatically once
+    /// the object is destroyed.
+    /// Returns a handle that can be used to cancel the task.
+    pub fn schedule_bound<T, F>(
+        &self,
+        when: Schedule,
+        target: WeakRef<T>,
+        mut task: F,
+    ) -> TaskHandle
+    where
+        T: ScriptClass,
+        F: FnMut(Ref<T>) + Send + 'static,
+    {
+        self.push(
+            when,
+            Box::new(move || match target.clone().upgrade() {
+                Some(target) => {
+                    task(target);
+                    TaskStatus::Alive
+                }
+                None => TaskStatus::Dead,
+            }),
+        )
+    }
+
+    /// Returns the current time of the scheduler clock.
+    #[inline]
+    pub fn now(&self) -> EngineTime {
+        EngineTime::try_from(lock(&self.clock).elapsed).unwrap_or_default()
+    }
+
+    /// Returns the number of frames the scheduler has processed so far.
+    #[inline]
+    pub fn frame(&self) -> u64 {
+        lock(&self.clock).frame
+    }
+
+    fn push(&self, when: Schedule, job: BoxedTask) -> TaskHandle {
+        let clock = *lock(&self.clock);
+        let handle = TaskHandle::default();
+        lock(&self.tasks).push(Task {
+            due: Due::first(when, &clock),
+            repeat: when,
+            job,
+            handle: handle.clone(),
+        });
+        handle
+    }
+
+    /// Attaches the scheduler to the update callback of the running state.
+    pub(crate) fn attach(&self, env: &SdkEnv) {
+        self.attached.call_once(|| {
+            let listener = ClosureListener::default().with_on_update(|_| {
+                if let Some(env) = ENV.get() {
+                    env.scheduler.tick();
+                }
+                StateHandlerResult::Finished
+            });
+            env.add_closure_listener(StateType::Running, listener);
+        });
+    }
+
+    pub(crate) fn tick(&self) {
+        let clock = lock(&self.clock).advance(sim_time());
+
+        // the tasks are taken out for the duration of the tick, this allows them to schedule
+        // new tasks without deadlocking
+        let mut tasks = mem::take(&mut *lock(&self.tasks));
+        tasks.retain_mut(|task| {
+            if task.handle.is_cancelled() {
+                return false;
+            }
+            if !task.due.is_due(&clock) {
+                return true;
+            }
+            // tasks that panic are removed
+            let status = catch_unwind("scheduled task", &mut task.job).unwrap_or(TaskStatus::Dead);
+            if status == TaskStatus::Dead {
+                task.handle.finish();
+                return false;
+            }
+            match task.due.next(task.repeat, &clock) {
+                Some(due) => {
+                    task.due = due;
+                    true
+                }
+                None => {
+                    task.handle.finish();
+                    false
+                }
+            }
+        });
+
+        let mut slot = lock(&self.tasks);
+        tasks.append(&mut slot);
+        *slot = tasks;
+    }
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self {
+            tasks: Mutex::default(),
+            clock: Mutex::default(),
+            attached: Once::new(),
+        }
+    }
+}
+
+impl fmt::Debug for Scheduler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Scheduler")
+            .field("tasks", &lock(&self.tasks).len())
+            .field("clock", &*lock(&self.clock))
+            .finish()
+    }
+}
+
+/// Describes when a scheduled task should be executed.
+#[derive(Debug, Clone, Copy)]
+pub enum Schedule {
+    /// Executes the task once, on the next frame.
+    NextFrame,
+    /// Executes the task once, after the specified amount of time has passed.
+    After(EngineTime),
+    /// Executes the task repeatedly, every N frames.
+    EveryFrames(NonZeroU32),
+    /// Executes the task repeatedly, each time the specified amount of time has passed.
+    Every(EngineTime),
+}
+
+impl Schedule {
+    /// Executes the task once, after the specified [`Duration`] has passed.
+    /// Returns an error if the duration can't be represented as [`EngineTime`].
+    #[inline]
+    pub fn after(delay: Duration) -> Result<Self, EngineTimeError> {
+        EngineTime::try_from(delay).map(Self::After)
+    }
+
+    /// Executes the task repeatedly, each time the specified [`Duration`] has passed.
+    /// Returns an error if the duration can't be represented as [`EngineTime`].
+    #[inline]
+    pub fn every(interval: Duration) -> Result<Self, EngineTimeError> {
+        EngineTime::try_from(interval).map(Self::Every)
+    }
+}
+
+/// A handle to a scheduled task. Dropping the handle does not cancel the task.
+#[derive(Debug, Default, Clone)]
+pub struct TaskHandle(Arc<TaskState>);
+
+impl TaskHandle {
+    /// Cancels the task. It will not be executed again.
+    #[inline]
+    pub fn cancel(&self) {
+        self.0.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns whether the task has been cancelled.
+    #[inline]
+    pub fn is_cancelled(&self) -> bool {
+        self.0.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Returns whether the task has finished, either because it was a one-shot task that has
+    /// been executed or because the object it was bound to has been destroyed.
+    #[inline]
+    pub fn is_finished(&self) -> bool {
+        self.0.finished.load(Ordering::Relaxed)
+    }
+
+    #[inline]
+    pub(crate) fn finish(&self) {
+        self.0.finished.store(true, Ordering::Relaxed);
+    }
+}
+
+#[derive(Debug, Default)]
+struct TaskState {
+    cancelled: AtomicBool,
+    finished: AtomicBool,
+}
+
+struct Task {
+    due: Due,
+    repeat: Schedule,
+    job: BoxedTask,
+    handle: TaskHandle,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TaskStatus {
+    Alive,
+    Dead,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Due {
+    Frame(u64),
+    Time(f64),
+}
+
+impl Due {
+    fn first(schedule: Schedule, clock: &Clock) -> Self {
+        match schedule {
+            Schedule::NextFrame => Self::Frame(clock.frame + 1),
+            Schedule::EveryFrames(n) => Self::Frame(clock.frame + u64::from(n.get())),
+            Schedule::After(delay) | Schedule::Every(delay) => {
+                Self::Time(clock.elapsed + delay.as_secs_f64())
+            }
+        }
+    }
+
+    fn next(self, schedule: Schedule, clock: &Clock) -> Option<Self> {
+        match schedule {
+            Schedule::NextFrame | Schedule::After(_) => None,
+            Schedule::EveryFrames(_) | Schedule::Every(_) => Some(Self::first(schedule, clock)),
+        }
+    }
+
+    #[inline]
+    fn is_due(self, clock: &Clock) -> bool {
+        match self {
+            Self::Frame(frame) => clock.frame >= frame,
+            Self::Time(time) => clock.elapsed >= time,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct Clock {
+    frame: u64,
+    elapsed: f64,
+    last_time: Option<f64>,
+}
+
+impl Clock {
+    /// Advances the clock by one frame. `now` is the current simulation time of the game, or
+    /// [`None`] if it's not available, in which case only the frame counter advances.
+    fn advance(&mut self, now: Option<f64>) -> Self {
+        if let Some(now) = now {
+            // the simulation time restarts when a save is loaded, it never goes back in the clock
+            if let Some(last) = self.last_time {
+                self.elapsed += (now - last).max(0.);
+            }
+            self.last_time = Some(now);
+        }
+        self.frame += 1;
+        *self
+    }
+}
+
+/// Returns the current simulation time of the game, which stops while the game is paused.
+fn sim_time() -> Option<f64> {
+    call!("GameInstance"::"GetSimTime;GameInstance" (GameInstance::new()) -> EngineTime)
+        .ok()
+        .map(|time| time.as_secs_f64())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroU32;
+    use std::time::Duration;
+
+    use super::{Clock, Due, Schedule};
+
+    #[test]
+    fn next_frame_is_due_after_one_frame() {
+        let mut clock = Clock::default();
+        let due = Due::first(Schedule::NextFrame, &clock);
+        assert!(!due.is_due(&clock));
+        assert!(due.is_due(&clock.advance(None)));
+        assert!(due.next(Schedule::NextFrame, &clock).is_none());
+    }
+
+    #[test]
+    fn every_frames_reschedules_from_current_frame() {
+        let mut clock = Clock::default();
+        let schedule = Schedule::EveryFrames(NonZeroU32::new(2).unwrap());
+        let due = Due::first(schedule, &clock);
+        assert!(!due.is_due(&clock.advance(None)));
+        assert!(due.is_due(&clock.advance(None)));
+
+        let next = due.next(schedule, &clock).unwrap();
+        assert!(!next.is_due(&clock.advance(None)));
+        assert!(next.is_due(&clock.advance(None)));
+    }
+
+    #[test]
+    fn delays_follow_simulation_time() {
+        let mut clock = Clock::default();
+        clock.advance(Some(100.));
+        let schedule = Schedule::after(Duration::from_secs(2)).unwrap();
+        let due = Due::first(schedule, &clock);
+
+        // the simulation time doesn't advance while the game is paused
+        assert!(!due.is_due(&clock.advance(Some(100.))));
+        assert!(!due.is_due(&clock.advance(None)));
+        assert!(!due.is_due(&clock.advance(Some(101.5))));
+        assert!(due.is_due(&clock.advance(Some(102.))));
+        assert!(due.next(schedule, &clock).is_none());
+    }
+
+    #[test]
+    fn clock_never_goes_back() {
+        let mut clock = Clock::default();
+        clock.advance(Some(50.));
+        clock.advance(Some(51.));
+        assert_eq!(clock.advance(Some(10.)).elapsed, 1.);
+        assert_eq!(clock.advance(Some(12.)).elapsed, 3.);
+    }
+
+    #[test]
+    fn every_reschedules_from_current_time() {
+        let mut clock = Clock::default();
+        clock.advance(Some(0.));
+        let schedule = Schedule::every(Duration::from_secs(1)).unwrap();
+        let due = Due::first(schedule, &clock);
+        assert!(due.is_due(&clock.advance(Some(1.5))));
+
+        let next = due.next(schedule, &clock).unwrap();
+        assert!(!next.is_due(&clock.advance(Some(2.))));
+        assert!(next.is_due(&clock.advance(Some(2.5))));
+    }
+}
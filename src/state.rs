@@ -0,0 +1,378 @@
+use std::sync::Mutex;
+use std::{fmt, mem};
+
+use crate::unwind::catch_unwind;
+use crate::{ENV, GameApp, SdkEnv, StateHandlerResult, StateListener, StateType, lock};
+
+type BoxedHandler<A = GameApp> = Box<dyn FnMut(&A) -> StateHandlerResult + Send>;
+
+/// A listener for state changes in the game application backed by Rust closures.
+/// Unlike [`StateListener`], the callbacks can capture their environment and any number of
+/// them can be attached to the same state type using [`SdkEnv::add_closure_listener`].
+///
+/// For more informations, see [game's lifecycle](https://docs.red4ext.com/mod-developers/custom-game-states#games-life-cycle).
+#[derive(Default)]
+pub struct ClosureListener {
+    on_enter: Option<BoxedHandler>,
+    on_update: Option<BoxedHandler>,
+    on_exit: Option<BoxedHandler>,
+}
+
+impl ClosureListener {
+    /// Sets a callback to be called when the state is entered.
+    ///
+    /// Called immediately after the state is activated. If the callback returns
+    /// [`StateHandlerResult::Running`], it will be called again on the next frame until it
+    /// finishes.
+    #[inline]
+    pub fn with_on_enter<F>(self, cb: F) -> Self
+    where
+        F: FnMut(&GameApp) -> StateHandlerResult + Send + 'static,
+    {
+        Self {
+            on_enter: Some(Box::new(cb)),
+            ..self
+        }
+    }
+
+    /// Sets a callback to be called when the state is updated.
+    ///
+    /// Called every frame for as long as the state is active.
+    #[inline]
+    pub fn with_on_update<F>(self, cb: F) -> Self
+    where
+        F: FnMut(&GameApp) -> StateHandlerResult + Send + 'static,
+    {
+        Self {
+            on_update: Some(Box::new(cb)),
+            ..self
+        }
+    }
+
+    /// Sets a callback to be called when the state is exited.
+    ///
+    /// Called when the state is ending. If the callback returns
+    /// [`StateHandlerResult::Running`], it will be called again on the next frame until it
+    /// finishes.
+    #[inline]
+    pub fn with_on_exit<F>(self, cb: F) -> Self
+    where
+        F: FnMut(&GameApp) -> StateHandlerResult + Send + 'static,
+    {
+        Self {
+            on_exit: Some(Box::new(cb)),
+            ..self
+        }
+    }
+}
+
+impl fmt::Debug for ClosureListener {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClosureListener")
+            .field("on_enter", &self.on_enter.is_some())
+            .field("on_update", &self.on_update.is_some())
+            .field("on_exit", &self.on_exit.is_some())
+            .finish()
+    }
+}
+
+/// A table of closure listeners owned by [`SdkEnv`]. A single raw listener is registered for
+/// each state type when the plugin is loaded, and it dispatches to all closures attached to
+/// that state.
+#[derive(Default)]
+pub(crate) struct StateListenerTable {
+    states: [StateSlots; STATE_COUNT],
+}
+
+impl StateListenerTable {
+    /// Registers the trampolines for all state types. This has to happen while the plugin is
+    /// being loaded, RED4ext does not accept new listeners after that.
+    pub(crate) fn install(env: &SdkEnv) {
+        env.register_listener(StateType::BaseInitialization, trampolines::<0>());
+        env.register_listener(StateType::Initialization, trampolines::<1>());
+        env.register_listener(StateType::Running, trampolines::<2>());
+        env.register_listener(StateType::Shutdown, trampolines::<3>());
+    }
+
+    pub(crate) fn add(&self, typ: StateType, listener: ClosureListener) {
+        let slots = &self.states[state_index(typ)];
+        let handlers = [
+            (Phase::Enter, listener.on_enter),
+            (Phase::Update, listener.on_update),
+            (Phase::Exit, listener.on_exit),
+        ];
+        for (phase, cb) in handlers {
+            if let Some(cb) = cb {
+                lock(slots.get(phase)).push(Handler::new(cb));
+            }
+        }
+    }
+
+    /// Removes all closures attached to the table.
+    pub(crate) fn clear(&self) {
+        for slots in &self.states {
+            for phase in [Phase::Enter, Phase::Update, Phase::Exit] {
+                let handlers = mem::take(&mut *lock(slots.get(phase)));
+                // the closures are dropped after the lock is released, they might use the table
+                drop(handlers);
+            }
+        }
+    }
+
+    fn dispatch(&self, index: usize, phase: Phase, app: &GameApp) -> StateHandlerResult {
+        let slot = self.states[index].get(phase);
+        // the handlers are taken out of the slot for the duration of the call, this allows
+        // callbacks to add new listeners without deadlocking
+        let mut handlers = mem::take(&mut *lock(slot));
+        let result = run_handlers(&mut handlers, STATE_TYPES[index], phase, app);
+
+        let mut slot = lock(slot);
+        handlers.append(&mut slot);
+        *slot = handlers;
+        result
+    }
+}
+
+/// Calls the handlers of a single state phase and returns the combined result. Handlers that
+/// panic are removed from the list.
+fn run_handlers<A>(
+    handlers: &mut Vec<Handler<A>>,
+    typ: StateType,
+    phase: Phase,
+    arg: &A,
+) -> StateHandlerResult {
+    let mut result = StateHandlerResult::Finished;
+    for handler in handlers.iter_mut() {
+        // update handlers are called every frame, enter and exit handlers are only called
+        // until they report that they have finished
+        if handler.finished && phase != Phase::Update {
+            continue;
+        }
+        let res = catch_unwind(format_args!("{typ:?} {phase:?} listener"), || {
+            (handler.cb)(arg)
+        });
+        let Some(res) = res else {
+            // handlers that panic are removed
+            handler.panicked = true;
+            continue;
+        };
+        handler.finished = res == StateHandlerResult::Finished;
+        if !handler.finished {
+            result = StateHandlerResult::Running;
+        }
+    }
+    handlers.retain(|h| !h.panicked);
+    if result == StateHandlerResult::Finished {
+        handlers.iter_mut().for_each(|h| h.finished = false);
+    }
+    result
+}
+
+/// Raw listeners added while the plugin is being loaded. They are only handed over to RED4ext
+/// once the plugin has loaded successfully, so that a plugin that fails to load does not leave
+/// any listeners behind.
+pub(crate) struct PendingListeners<L = StateListener>(Mutex<Option<Vec<(StateType, L)>>>);
+
+impl<L> PendingListeners<L> {
+    /// Defers a listener until the plugin has loaded. The listener is returned back if the
+    /// plugin is not being loaded anymore.
+    pub(crate) fn defer(&self, typ: StateType, listener: L) -> Result<(), L> {
+        match &mut *lock(&self.0) {
+            Some(pending) => {
+                pending.push((typ, listener));
+                Ok(())
+            }
+            None => Err(listener),
+        }
+    }
+
+    /// Marks the end of loading and returns the deferred listeners in the order they were added.
+    pub(crate) fn close(&self) -> Vec<(StateType, L)> {
+        lock(&self.0).take().unwrap_or_default()
+    }
+}
+
+impl<L> Default for PendingListeners<L> {
+    #[inline]
+    fn default() -> Self {
+        Self(Mutex::new(Some(Vec::new())))
+    }
+}
+
+impl<L> fmt::Debug for PendingListeners<L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PendingListeners").finish_non_exhaustive()
+    }
+}
+
+impl fmt::Debug for StateListenerTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StateListenerTable").finish_non_exhaustive()
+    }
+}
+
+const STATE_COUNT: usize = 4;
+const STATE_TYPES: [StateType; STATE_COUNT] = [
+    StateType::BaseInitialization,
+    StateType::Initialization,
+    StateType::Running,
+    StateType::Shutdown,
+];
+
+#[inline]
+fn state_index(typ: StateType) -> usize {
+    match typ {
+        StateType::BaseInitialization => 0,
+        StateType::Initialization => 1,
+        StateType::Running => 2,
+        StateType::Shutdown => 3,
+    }
+}
+
+#[derive(Default)]
+struct StateSlots {
+    on_enter: Mutex<Vec<Handler>>,
+    on_update: Mutex<Vec<Handler>>,
+    on_exit: Mutex<Vec<Handler>>,
+}
+
+impl StateSlots {
+    #[inline]
+    fn get(&self, phase: Phase) -> &Mutex<Vec<Handler>> {
+        match phase {
+            Phase::Enter => &self.on_enter,
+            Phase::Update => &self.on_update,
+            Phase::Exit => &self.on_exit,
+        }
+    }
+}
+
+struct Handler<A = GameApp> {
+    cb: BoxedHandler<A>,
+    finished: bool,
+    panicked: bool,
+}
+
+impl<A> Handler<A> {
+    #[inline]
+    fn new(cb: BoxedHandler<A>) -> Self {
+        Self {
+            cb,
+            finished: false,
+            panicked: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Phase {
+    Enter,
+    Update,
+    Exit,
+}
+
+fn trampolines<const I: usize>() -> StateListener {
+    StateListener::default()
+        .with_on_enter(on_enter::<I>)
+        .with_on_update(on_update::<I>)
+        .with_on_exit(on_exit::<I>)
+}
+
+unsafe extern "C" fn on_enter<const I: usize>(app: &GameApp) -> StateHandlerResult {
+    dispatch(I, Phase::Enter, app)
+}
+
+unsafe extern "C" fn on_update<const I: usize>(app: &GameApp) -> StateHandlerResult {
+    dispatch(I, Phase::Update, app)
+}
+
+unsafe extern "C" fn on_exit<const I: usize>(app: &GameApp) -> StateHandlerResult {
+    dispatch(I, Phase::Exit, app)
+}
+
+#[inline]
+fn dispatch(index: usize, phase: Phase, app: &GameApp) -> StateHandlerResult {
+    match ENV.get() {
+        Some(env) => env.state_listeners.dispatch(index, phase, app),
+        None => StateHandlerResult::Finished,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use super::{Handler, PendingListeners, Phase, run_handlers};
+    use crate::{StateHandlerResult, StateType};
+
+    fn counting_handler(calls: &Arc<AtomicU32>, finish_after: u32) -> Handler<()> {
+        let calls = calls.clone();
+        Handler::new(Box::new(move |_: &()| {
+            if calls.fetch_add(1, Ordering::Relaxed) + 1 >= finish_after {
+                StateHandlerResult::Finished
+            } else {
+                StateHandlerResult::Running
+            }
+        }))
+    }
+
+    #[test]
+    fn enter_handlers_run_until_finished() {
+        let (slow, fast) = (Arc::new(AtomicU32::new(0)), Arc::new(AtomicU32::new(0)));
+        let mut handlers = vec![counting_handler(&slow, 2), counting_handler(&fast, 1)];
+
+        let res = run_handlers(&mut handlers, StateType::Running, Phase::Enter, &());
+        assert_eq!(res, StateHandlerResult::Running);
+        let res = run_handlers(&mut handlers, StateType::Running, Phase::Enter, &());
+        assert_eq!(res, StateHandlerResult::Finished);
+
+        assert_eq!(slow.load(Ordering::Relaxed), 2);
+        // finished handlers are skipped until all of them have finished
+        assert_eq!(fast.load(Ordering::Relaxed), 1);
+        assert!(handlers.iter().all(|h| !h.finished));
+    }
+
+    #[test]
+    fn update_handlers_run_every_frame() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let mut handlers = vec![counting_handler(&calls, 1)];
+        for _ in 0..3 {
+            let res = run_handlers(&mut handlers, StateType::Running, Phase::Update, &());
+            assert_eq!(res, StateHandlerResult::Finished);
+        }
+        assert_eq!(calls.load(Ordering::Relaxed), 3);
+    }
+
+    #[test]
+    fn panicking_handlers_are_removed() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let mut handlers = vec![
+            Handler::new(Box::new(|_: &()| -> StateHandlerResult {
+                panic!("listener failure")
+            })),
+            counting_handler(&calls, 1),
+        ];
+        let res = run_handlers(&mut handlers, StateType::Running, Phase::Update, &());
+        assert_eq!(res, StateHandlerResult::Finished);
+        assert_eq!(handlers.len(), 1);
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn pending_listeners_are_released_in_order() {
+        let pending = PendingListeners::default();
+        assert_eq!(pending.defer(StateType::Running, "first"), Ok(()));
+        assert_eq!(pending.defer(StateType::Shutdown, "second"), Ok(()));
+        assert_eq!(
+            pending.close(),
+            [
+                (StateType::Running, "first"),
+                (StateType::Shutdown, "second")
+            ]
+        );
+        // listeners added after loading are not deferred anymore
+        assert_eq!(pending.defer(StateType::Running, "third"), Err("third"));
+        assert!(pending.close().is_empty());
+    }
+}
@@ -0,0 +1,324 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, Once};
+use std::{fmt, mem};
+
+use crate::invocable::AsReceiver;
+use crate::types::{IScriptable, WeakRef};
+use crate::{ClosureListener, ENV, StateHandlerResult, StateType, lock};
+
+/// Storages that have had entries inserted, swept on every update of the running state.
+static STORAGES: Mutex<Vec<&'static dyn Sweep>> = Mutex::new(Vec::new());
+static SWEEP_LISTENER: Once = Once::new();
+
+trait Sweep: Sync {
+    /// Removes the entries of objects that have been destroyed.
+    fn sweep(&self);
+}
+
+/// A trait for references to the objects of a storage, which tell whether the object has been
+/// destroyed.
+trait Object {
+    fn is_expired(&self) -> bool;
+}
+
+impl Object for WeakRef<IScriptable> {
+    #[inline]
+    fn is_expired(&self) -> bool {
+        WeakRef::is_expired(self)
+    }
+}
+
+fn sweep_all() {
+    let storages = lock(&STORAGES).clone();
+    for storage in storages {
+        storage.sweep();
+    }
+}
+
+/// A side table associating Rust values with game objects, keyed by the identity of the
+/// [`IScriptable`] instance. It can be used to attach state to objects whose layout can't be
+/// changed, such as classes defined by the game.
+///
+/// An entry is removed once its object is destroyed, that is when the strong reference count
+/// of the object drops to zero. Destroyed objects are swept on every update of the
+/// [`Running`](StateType::Running) state, and their entries are never returned by lookups.
+///
+/// The storage is meant to be declared as a `static`. Every plugin has its own statics, so
+/// plugins never observe each other's entries, even if they store values of the same type.
+///
+/// Objects are accepted as anything implementing [`AsReceiver`], which includes [`Ref`]s and
+/// native types implementing `AsRef<IScriptable>`. Exported methods can access the storage of
+/// their receiver by implementing `AsRef<IScriptable>` for the class.
+///
+/// # Example
+/// ```rust
+/// use std::sync::atomic::{AtomicU32, Ordering};
+///
+/// use red4ext_rs::ObjectStorage;
+/// use red4ext_rs::types::{IScriptable, Ref};
+///
+/// #[derive(Debug, Default)]
+/// struct Visits(AtomicU32);
+///
+/// static VISITS: ObjectStorage<Visits> = ObjectStorage::new();
+///
+/// fn visit(object: Ref<IScriptable>) -> u32 {
+///     VISITS
+///         .get_or_insert(&object)
+///         .map_or(0, |visits| visits.0.fetch_add(1, Ordering::Relaxed) + 1)
+/// }
+/// ```
+///
+/// [`Ref`]: crate::types::Ref
+pub struct ObjectStorage<T> {
+    entries: Mutex<Entries<T>>,
+    registered: Once,
+}
+
+impl<T> ObjectStorage<T> {
+    /// Creates a new empty storage.
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            entries: Mutex::new(Entries::new()),
+            registered: Once::new(),
+        }
+    }
+
+    /// Returns the number of entries in the storage, including the entries of destroyed
+    /// objects that have not been swept yet.
+    #[inline]
+    pub fn len(&self) -> usize {
+        lock(&self.entries).0.len()
+    }
+
+    /// Returns `true` if the storage contains no entries.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        lock(&self.entries).0.is_empty()
+    }
+
+    /// Removes all entries from the storage.
+    pub fn clear(&self) {
+        let entries = mem::take(&mut lock(&self.entries).0);
+        // values are dropped after the lock is released, their destructors might use the storage
+        drop(entries);
+    }
+}
+
+impl<T: Send + Sync + 'static> ObjectStorage<T> {
+    /// Returns the value associated with the object.
+    /// Returns [`None`] if there is no value, or if the reference is null.
+    pub fn get(&self, object: &impl AsReceiver) -> Option<Arc<T>> {
+        let key = Self::key(object.as_receiver().ok()?);
+        lock(&self.entries).get(key)
+    }
+
+    /// Returns the value associated with the object, inserting the default value if there is
+    /// none. Returns [`None`] if the reference is null.
+    #[inline]
+    pub fn get_or_insert(&'static self, object: &impl AsReceiver) -> Option<Arc<T>>
+    where
+        T: Default,
+    {
+        self.get_or_insert_with(object, T::default)
+    }
+
+    /// Returns the value associated with the object, inserting the value returned by `init` if
+    /// there is none. Returns [`None`] if the reference is null.
+    pub fn get_or_insert_with(
+        &'static self,
+        object: &impl AsReceiver,
+        init: impl FnOnce() -> T,
+    ) -> Option<Arc<T>> {
+        if let Some(value) = self.get(object) {
+            return Some(value);
+        }
+        let instance = object.as_receiver().ok()?;
+        let weak = instance.as_serializable().inner_ref::<IScriptable>()?;
+        // the value is created without holding the lock, so that `init` can use the storage
+        let value = Arc::new(init());
+
+        let (value, unused) = lock(&self.entries).get_or_insert(Self::key(instance), weak, value);
+        // values are dropped after the lock is released, their destructors might use the storage
+        drop(unused);
+
+        self.register();
+        Some(value)
+    }
+
+    /// Removes the value associated with the object and returns it.
+    /// Returns [`None`] if there is no value, or if the reference is null.
+    pub fn remove(&self, object: &impl AsReceiver) -> Option<Arc<T>> {
+        let key = Self::key(object.as_receiver().ok()?);
+        let entry = lock(&self.entries).0.remove(&key)?;
+        (!entry.object.is_expired()).then_some(entry.value)
+    }
+
+    #[inline]
+    fn key(instance: &IScriptable) -> usize {
+        instance as *const IScriptable as usize
+    }
+
+    fn register(&'static self) {
+        self.registered.call_once(|| lock(&STORAGES).push(self));
+        // the listener is installed by the first storage used while the plugin is loaded
+        let Some(env) = ENV.get() else {
+            return;
+        };
+        SWEEP_LISTENER.call_once(|| {
+            let listener = ClosureListener::default().with_on_update(|_| {
+                sweep_all();
+                StateHandlerResult::Finished
+            });
+            env.add_closure_listener(StateType::Running, listener);
+        });
+    }
+}
+
+impl<T: Send + Sync> Sweep for ObjectStorage<T> {
+    fn sweep(&self) {
+        let removed = lock(&self.entries).sweep();
+        // values are dropped after the lock is released, their destructors might use the storage
+        drop(removed);
+    }
+}
+
+impl<T> Default for ObjectStorage<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> fmt::Debug for ObjectStorage<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ObjectStorage")
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+/// The entries of a storage, keyed by the address of their object.
+struct Entries<T, O = WeakRef<IScriptable>>(BTreeMap<usize, Entry<T, O>>);
+
+impl<T, O: Object> Entries<T, O> {
+    #[inline]
+    const fn new() -> Self {
+        Self(BTreeMap::new())
+    }
+
+    /// Returns the value associated with the key, unless its object has been destroyed.
+    fn get(&self, key: usize) -> Option<Arc<T>> {
+        let entry = self.0.get(&key)?;
+        (!entry.object.is_expired()).then(|| entry.value.clone())
+    }
+
+    /// Returns the value associated with the key, inserting the given value if there is none.
+    /// Also returns the entry that has been replaced, or the given one if it was not inserted,
+    /// so that it can be dropped after the lock is released.
+    fn get_or_insert(
+        &mut self,
+        key: usize,
+        object: O,
+        value: Arc<T>,
+    ) -> (Arc<T>, Option<Entry<T, O>>) {
+        let entry = Entry {
+            object,
+            value: value.clone(),
+        };
+        match self.get(key) {
+            Some(existing) => (existing, Some(entry)),
+            // an expired entry belongs to a destroyed object that occupied the same address
+            None => (value, self.0.insert(key, entry)),
+        }
+    }
+
+    /// Removes the entries of objects that have been destroyed and returns them.
+    fn sweep(&mut self) -> Vec<Entry<T, O>> {
+        let expired = self
+            .0
+            .iter()
+            .filter(|(_, entry)| entry.object.is_expired())
+            .map(|(&key, _)| key)
+            .collect::<Vec<_>>();
+        expired
+            .into_iter()
+            .filter_map(|key| self.0.remove(&key))
+            .collect()
+    }
+}
+
+struct Entry<T, O = WeakRef<IScriptable>> {
+    object: O,
+    value: Arc<T>,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use super::{Entries, Object};
+
+    #[derive(Default, Clone)]
+    struct TestObject(Arc<AtomicBool>);
+
+    impl TestObject {
+        fn destroy(&self) {
+            self.0.store(true, Ordering::Relaxed);
+        }
+    }
+
+    impl Object for TestObject {
+        fn is_expired(&self) -> bool {
+            self.0.load(Ordering::Relaxed)
+        }
+    }
+
+    #[test]
+    fn live_entries_are_kept() {
+        let mut entries = Entries::<u32, TestObject>::new();
+        let object = TestObject::default();
+
+        let (value, unused) = entries.get_or_insert(1, object.clone(), Arc::new(1));
+        assert_eq!(*value, 1);
+        assert!(unused.is_none());
+
+        let (value, unused) = entries.get_or_insert(1, object, Arc::new(2));
+        assert_eq!(*value, 1);
+        assert_eq!(unused.map(|entry| *entry.value), Some(2));
+        assert_eq!(entries.get(1).as_deref(), Some(&1));
+    }
+
+    #[test]
+    fn reused_key_replaces_expired_entry() {
+        let mut entries = Entries::<u32, TestObject>::new();
+        let destroyed = TestObject::default();
+        entries.get_or_insert(1, destroyed.clone(), Arc::new(1));
+        destroyed.destroy();
+        assert_eq!(entries.get(1), None);
+
+        // a new object allocated at the same address gets a fresh value
+        let (value, replaced) = entries.get_or_insert(1, TestObject::default(), Arc::new(2));
+        assert_eq!(*value, 2);
+        assert_eq!(replaced.map(|entry| *entry.value), Some(1));
+        assert_eq!(entries.get(1).as_deref(), Some(&2));
+    }
+
+    #[test]
+    fn sweep_removes_expired_entries() {
+        let mut entries = Entries::<u32, TestObject>::new();
+        let (first, second) = (TestObject::default(), TestObject::default());
+        entries.get_or_insert(1, first.clone(), Arc::new(1));
+        entries.get_or_insert(2, second, Arc::new(2));
+
+        assert!(entries.sweep().is_empty());
+        first.destroy();
+        let removed = entries.sweep();
+        assert_eq!(removed.len(), 1);
+        assert_eq!(*removed[0].value, 1);
+        assert_eq!(entries.0.len(), 1);
+        assert_eq!(entries.get(2).as_deref(), Some(&2));
+    }
+}
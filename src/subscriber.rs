@@ -0,0 +1,110 @@
+use std::fmt::{self, Write};
+
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Level, Metadata, Subscriber};
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+
+use crate::SdkEnv;
+
+/// Installs a global `tracing` subscriber forwarding events to the RED4ext logger.
+pub(crate) fn install(env: &'static SdkEnv) {
+    let subscriber = tracing_subscriber::registry().with(SdkLayer(env));
+    if tracing::subscriber::set_global_default(subscriber).is_err() {
+        env.warn("a global tracing subscriber has already been set");
+    }
+}
+
+/// A layer that formats events along with the spans they occurred in, e.g.
+/// `load{name=foo}: message key=value`, and forwards them to the RED4ext logger.
+struct SdkLayer(&'static SdkEnv);
+
+impl<S> Layer<S> for SdkLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn enabled(&self, metadata: &Metadata<'_>, _ctx: Context<'_, S>) -> bool {
+        self.0
+            .log_enabled(metadata.target(), (*metadata.level()).into())
+    }
+
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut fields = SpanFields::default();
+        attrs.record(&mut FieldWriter::new(&mut fields.0));
+        span.extensions_mut().insert(fields);
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        if let Some(fields) = span.extensions_mut().get_mut::<SpanFields>() {
+            values.record(&mut FieldWriter::new(&mut fields.0));
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let mut message = String::new();
+        for span in ctx
+            .event_scope(event)
+            .into_iter()
+            .flat_map(|s| s.from_root())
+        {
+            message.push_str(span.name());
+            if let Some(fields) = span.extensions().get::<SpanFields>()
+                && !fields.0.is_empty()
+            {
+                write!(message, "{{{}}}", fields.0).ok();
+            }
+            message.push_str(": ");
+        }
+
+        let start = message.len();
+        event.record(&mut FieldWriter {
+            buf: &mut message,
+            start,
+        });
+
+        match *event.metadata().level() {
+            Level::ERROR => self.0.error(message),
+            Level::WARN => self.0.warn(message),
+            Level::INFO => self.0.info(message),
+            Level::DEBUG => self.0.debug(message),
+            _ => self.0.trace(message),
+        }
+    }
+}
+
+/// Formatted fields of a span, stored in its extensions.
+#[derive(Debug, Default)]
+struct SpanFields(String);
+
+struct FieldWriter<'a> {
+    buf: &'a mut String,
+    // the position at which the fields start, used to decide whether a separator is needed
+    start: usize,
+}
+
+impl<'a> FieldWriter<'a> {
+    #[inline]
+    fn new(buf: &'a mut String) -> Self {
+        Self { buf, start: 0 }
+    }
+}
+
+impl Visit for FieldWriter<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if self.buf.len() > self.start {
+            self.buf.push(' ');
+        }
+        if field.name() == "message" {
+            write!(self.buf, "{value:?}").ok();
+        } else {
+            write!(self.buf, "{}={value:?}", field.name()).ok();
+        }
+    }
+}
@@ -2,9 +2,9 @@ use std::{mem, ptr};
 
 use crate::raw::root::RED4ext as red;
 use crate::types::{
-    Bitfield, CName, Class, ClassFlags, ClassHandle, Enum, Function, GameEngine, GlobalFunction,
-    PoolRef, RedArray, RedHashMap, Ref, RwSpinLockReadGuard, RwSpinLockWriteGuard,
-    ScriptableSystem, Type,
+    Bitfield, BitfieldHandle, CName, Class, ClassFlags, ClassHandle, Enum, EnumHandle, Function,
+    GameEngine, GlobalFunction, PoolRef, RedArray, RedHashMap, Ref, RwSpinLockReadGuard,
+    RwSpinLockWriteGuard, ScriptableSystem, Type,
 };
 
 /// The RTTI system containing information about all types in the game.
@@ -264,13 +264,30 @@ impl RttiSystemMut {
     /// The handle can be obtained from
     /// [`NativeClass::new_handle`](crate::types::NativeClass::new_handle).
     pub fn register_class(&mut self, mut class: ClassHandle) {
+        let name = class.as_ref().name();
+        self.insert_type(name, class.as_mut().as_type_mut());
+    }
+
+    /// Register a new [`EnumHandle`] with the RTTI system.
+    /// The handle can be obtained from [`Enum::new_handle`].
+    pub fn register_enum(&mut self, mut enum_: EnumHandle) {
+        let name = enum_.as_ref().name();
+        self.insert_type(name, enum_.as_mut().as_type_mut());
+    }
+
+    /// Register a new [`BitfieldHandle`] with the RTTI system.
+    /// The handle can be obtained from [`Bitfield::new_handle`].
+    pub fn register_bitfield(&mut self, mut bitfield: BitfieldHandle) {
+        let name = bitfield.as_ref().name();
+        self.insert_type(name, bitfield.as_mut().as_type_mut());
+    }
+
+    fn insert_type(&mut self, name: CName, ty: &mut Type) {
         // implemented manually to avoid the game trying to obtain the type lock
         let id = unsafe { red::RTTIRegistrator::GetNextId() };
-        self.type_map()
-            .insert(class.as_ref().name(), class.as_mut().as_type_mut());
-        self.type_by_id_map()
-            .insert(id, class.as_mut().as_type_mut());
-        self.type_id_map().insert(class.as_ref().name(), id);
+        self.type_map().insert(name, &mut *ty);
+        self.type_by_id_map().insert(id, ty);
+        self.type_id_map().insert(name, id);
     }
 
     /// Register a new [`GlobalFunction`] with the RTTI system.
@@ -282,6 +299,38 @@ impl RttiSystemMut {
         mem::forget(function);
     }
 
+    /// Unregister a class previously registered with [`register_class`](Self::register_class).
+    /// The class itself is not freed, since there might still be instances referring to it.
+    /// Returns `false` if the class was not registered.
+    #[inline]
+    pub fn unregister_class(&mut self, name: CName) -> bool {
+        self.unregister_type(name)
+    }
+
+    /// Unregister a type previously registered with [`register_class`](Self::register_class),
+    /// [`register_enum`](Self::register_enum) or [`register_bitfield`](Self::register_bitfield).
+    /// The type itself is not freed, since there might still be values referring to it.
+    /// Returns `false` if the type was not registered.
+    pub fn unregister_type(&mut self, name: CName) -> bool {
+        // implemented manually to mirror insert_type
+        let (types, types_by_id, type_ids) = self.split_types();
+        let removed = types.remove(&name).is_some();
+        match type_ids.remove(&name) {
+            Some(id) => types_by_id.remove(&id).is_some() || removed,
+            None => removed,
+        }
+    }
+
+    /// Unregister a [`GlobalFunction`] previously registered with
+    /// [`register_function`](Self::register_function).
+    ///
+    /// # Safety
+    /// The function must have been registered with the RTTI system.
+    #[inline]
+    pub unsafe fn unregister_function(&mut self, function: *const GlobalFunction) {
+        unsafe { (self.vft().unregister_function)(self as *const _ as *const RttiSystem, function) }
+    }
+
     #[inline]
     fn type_map(&mut self) -> &mut RedHashMap<CName, &mut Type> {
         unsafe { &mut *(&mut self.0.types as *mut _ as *mut RedHashMap<CName, &mut Type>) }
@@ -3,7 +3,7 @@ pub use opt::Opt;
 mod cruid;
 pub use cruid::Cruid;
 mod engine_time;
-pub use engine_time::EngineTime;
+pub use engine_time::{EngineTime, EngineTimeError};
 mod entity_id;
 pub use entity_id::EntityId;
 mod game_time;
@@ -26,11 +26,11 @@ mod cname;
 pub use cname::{CName, CNamePool};
 mod rtti;
 pub use rtti::{
-    ArrayType, Bitfield, Class, ClassFlags, ClassHandle, CurveType, Enum, Function, FunctionFlags,
-    FunctionHandler, GlobalFunction, IScriptable, ISerializable, Method, NativeArrayType,
-    NativeClass, PointerType, Property, PropertyFlags, RaRefType, RefType, ResourceRefType,
-    ScriptRefType, StaticArrayType, StaticMethod, TaggedType, Type, TypeKind, ValueContainer,
-    ValuePtr, WeakRefType,
+    ArrayType, Bitfield, BitfieldHandle, Class, ClassFlags, ClassHandle, CurveType, Enum,
+    EnumHandle, Function, FunctionFlags, FunctionHandler, GlobalFunction, IScriptable,
+    ISerializable, Method, NativeArrayType, NativeClass, PointerType, Property, PropertyFlags,
+    RaRefType, RefType, ResourceRefType, ScriptRefType, StaticArrayType, StaticMethod, TaggedType,
+    Type, TypeKind, ValueContainer, ValuePtr, WeakRefType,
 };
 mod bytecode;
 pub use bytecode::{
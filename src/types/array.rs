@@ -68,6 +68,27 @@ impl<T> RedArray<T> {
         self.0.size = len + 1;
     }
 
+    /// Retains only the elements for which the predicate returns `true`, preserving the order
+    /// of the retained elements.
+    pub fn retain(&mut self, mut f: impl FnMut(&T) -> bool) {
+        let len = self.len() as usize;
+        let mut retained = 0;
+        for i in 0..len {
+            unsafe {
+                let elem = self.0.entries.add(i);
+                if f(&*elem) {
+                    if retained != i {
+                        ptr::copy_nonoverlapping(elem, self.0.entries.add(retained), 1);
+                    }
+                    retained += 1;
+                } else {
+                    ptr::drop_in_place(elem);
+                }
+            }
+        }
+        self.0.size = retained as u32;
+    }
+
     /// Reserve capacity for at least `additional` more elements to be inserted.
     pub fn reserve(&mut self, additional: u32) {
         let expected = self.len() + additional;
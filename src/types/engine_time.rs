@@ -1,6 +1,6 @@
 use std::time::Duration;
 
-#[derive(Default, Clone, Copy)]
+#[derive(Debug, Default, Clone, Copy)]
 #[repr(transparent)]
 pub struct EngineTime(f64);
 
@@ -49,6 +49,16 @@ impl<K, V> RedHashMap<K, V> {
         None
     }
 
+    pub fn remove(&mut self, key: &K) -> Option<V>
+    where
+        K: Hash + PartialEq,
+    {
+        let hash = key.hash();
+        let value = self.unlink_by_hash(hash)?;
+        self.0.size -= 1;
+        Some(value)
+    }
+
     #[inline]
     pub fn iter(&self) -> Iter<'_, K, V> {
         self.into_iter()
@@ -92,6 +102,36 @@ impl<K, V> RedHashMap<K, V> {
         None
     }
 
+    fn unlink_by_hash(&mut self, hash: u32) -> Option<V> {
+        let (node_list, index_table) = self.split_mut();
+        let len = index_table.len() as u32;
+        let bucket = index_table.get_mut(hash.checked_rem(len)? as usize)?;
+
+        let mut prev: Option<*mut red::HashMap_Node<K, V>> = None;
+        let mut cur = *bucket;
+        while cur != INVALID_INDEX {
+            let node = unsafe { node_list.nodes.add(cur as usize) };
+            let next = unsafe { (*node).next };
+            if unsafe { (*node).hashedKey } == hash {
+                match prev {
+                    Some(prev) => unsafe { (*prev).next = next },
+                    None => *bucket = next,
+                }
+                let value = unsafe {
+                    ptr::drop_in_place(&mut (*node).key);
+                    ptr::read(&(*node).value)
+                };
+                // the node is returned to the free list
+                unsafe { (*node).next = node_list.nextIdx };
+                node_list.nextIdx = cur;
+                return Some(value);
+            }
+            prev = Some(node);
+            cur = next;
+        }
+        None
+    }
+
     fn realloc(&mut self, new_capacity: u32) {
         let new_cap_bytes = new_capacity as usize
             * (mem::size_of::<red::HashMap_Node<K, V>>() + mem::size_of::<u32>());
@@ -281,3 +321,69 @@ impl Hash for u32 {
         *self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::mem;
+
+    use super::{INVALID_INDEX, RedHashMap};
+    use crate::raw::root::RED4ext as red;
+
+    type Node = red::HashMap_Node<u32, u32>;
+
+    /// Runs a test against a map backed by a preallocated buffer, the map must not grow beyond
+    /// its capacity since it has no allocator.
+    fn with_map(capacity: u32, test: impl FnOnce(&mut RedHashMap<u32, u32>)) {
+        let mut nodes = Vec::<Node>::with_capacity(capacity as usize);
+        let mut indexes = vec![INVALID_INDEX; capacity as usize];
+        let mut map = RedHashMap(red::HashMap {
+            nodeList: red::HashMap_NodeList {
+                nodes: nodes.as_mut_ptr(),
+                capacity,
+                stride: mem::size_of::<Node>() as _,
+                ..Default::default()
+            },
+            indexTable: indexes.as_mut_ptr(),
+            capacity,
+            ..Default::default()
+        });
+        test(&mut map);
+    }
+
+    #[test]
+    fn remove_from_bucket_chain() {
+        with_map(8, |map| {
+            // all keys land in the same bucket
+            for key in [1, 9, 17] {
+                assert_eq!(map.insert(key, key * 10), None);
+            }
+            assert_eq!(map.remove(&9), Some(90));
+            assert_eq!(map.remove(&9), None);
+            assert_eq!(map.size(), 2);
+            assert_eq!(map.get(&1), Some(&10));
+            assert_eq!(map.get(&17), Some(&170));
+
+            assert_eq!(map.remove(&17), Some(170));
+            assert_eq!(map.remove(&1), Some(10));
+            assert_eq!(map.size(), 0);
+            assert_eq!(map.iter().count(), 0);
+        });
+    }
+
+    #[test]
+    fn removed_nodes_are_reused() {
+        with_map(8, |map| {
+            for key in 0..3 {
+                map.insert(key, key);
+            }
+            assert_eq!(map.remove(&1), Some(1));
+            map.insert(5, 5);
+            // the node of the removed entry is taken from the free list
+            assert_eq!(map.0.nodeList.size, 3);
+
+            let mut entries = map.iter().map(|(&k, &v)| (k, v)).collect::<Vec<_>>();
+            entries.sort_unstable();
+            assert_eq!(entries, [(0, 0), (2, 2), (5, 5)]);
+        });
+    }
+}
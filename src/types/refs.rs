@@ -3,7 +3,7 @@ use std::sync::atomic::{AtomicU32, Ordering};
 use std::{mem, ptr};
 
 use super::{CName, ISerializable, Type};
-use crate::class::{NativeType, ScriptClass};
+use crate::class::{NativeType, ScriptClass, Subclass};
 use crate::raw::root::RED4ext as red;
 use crate::repr::NativeRepr;
 use crate::systems::RttiSystem;
@@ -87,6 +87,16 @@ impl<T: ScriptClass> Ref<T> {
         inst.is_a::<U>().then(|| unsafe { mem::transmute(self) })
     }
 
+    /// Converts the reference to a reference of the base class. Unlike [`Ref::cast`], this
+    /// conversion never fails.
+    #[inline]
+    pub fn upcast(self) -> Ref<T::Base>
+    where
+        T: Subclass,
+    {
+        unsafe { mem::transmute(self) }
+    }
+
     /// Returns whether the reference is null.
     #[inline]
     pub fn is_null(&self) -> bool {
@@ -166,6 +176,15 @@ impl<T: ScriptClass> WeakRef<T> {
     pub fn upgrade(self) -> Option<Ref<T>> {
         self.0.inc_strong_if_non_zero().then(|| Ref(self.0.clone()))
     }
+
+    /// Returns whether the referenced object has been destroyed, which is the case when the
+    /// strong reference count is zero.
+    #[inline]
+    pub fn is_expired(&self) -> bool {
+        self.0
+            .ref_count()
+            .is_none_or(|cnt| cnt.strong().load(Ordering::Relaxed) == 0)
+    }
 }
 
 impl<T: ScriptClass> Default for WeakRef<T> {
@@ -342,6 +361,12 @@ impl<'a, T: NativeRepr> ScriptRef<'a, T> {
         unsafe { self.0.ref_.as_ref() }
     }
 
+    /// Returns a mutable reference to the value being referenced.
+    #[inline]
+    pub fn value_mut(&mut self) -> Option<&mut T> {
+        unsafe { self.0.ref_.as_mut() }
+    }
+
     /// Returns the type of the value being referenced.
     #[inline]
     pub fn inner_type(&self) -> &Type {
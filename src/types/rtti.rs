@@ -1,4 +1,4 @@
-use std::ffi::CStr;
+use std::ffi::{CStr, CString};
 use std::marker::PhantomData;
 use std::ptr::NonNull;
 use std::{fmt, iter, mem, ptr, slice};
@@ -11,7 +11,7 @@ use crate::invocable::{Args, InvokeError};
 use crate::raw::root::RED4ext as red;
 use crate::repr::{FromRepr, NativeRepr};
 use crate::types::PtrEq;
-use crate::{ScriptClass, VoidPtr, check_invariant, class_kind};
+use crate::{ScriptClass, VoidPtr, class_kind};
 
 /// A handler for function calls.
 pub type FunctionHandler<C, R> = extern "C" fn(&C, &mut StackFrame, R, i64);
@@ -309,6 +309,16 @@ impl Class {
         unsafe { (self.0.parent as *const Class).as_ref() }
     }
 
+    /// Sets the base class and updates the layout of the class to accommodate it. The size and
+    /// the alignment are raised to at least those of the base, and the value holder is grown to
+    /// fit the scripted properties of the base.
+    pub fn set_base(&mut self, base: &Class) {
+        self.0.parent = base.as_raw() as *const _ as *mut red::CClass;
+        self.0.size = self.0.size.max(base.size());
+        self.0.alignment = self.0.alignment.max(base.alignment());
+        self.0.holderSize = self.0.holderSize.max(base.holder_size());
+    }
+
     #[inline]
     pub fn base_iter(&self) -> impl Iterator<Item = &Class> {
         iter::successors(self.base(), |class| class.base())
@@ -345,6 +355,35 @@ impl Class {
         mem::forget(func);
     }
 
+    /// Removes a method with the given full name from this class. The method itself is not
+    /// freed, since it might still be referenced elsewhere.
+    /// Returns `true` if the method was found and removed.
+    pub fn remove_method(&mut self, name: CName) -> bool {
+        let len = self.methods().len();
+        self.methods_mut()
+            .retain(|method| method.as_function().name() != name);
+        self.method_map_mut().remove(&name);
+        self.methods().len() != len
+    }
+
+    /// Removes a static method with the given full name from this class. The method itself is
+    /// not freed, since it might still be referenced elsewhere.
+    /// Returns `true` if the method was found and removed.
+    pub fn remove_static_method(&mut self, name: CName) -> bool {
+        let len = self.static_methods().len();
+        self.static_methods_mut()
+            .retain(|method| method.as_function().name() != name);
+        // static methods looked up by name are cached in the name map as well
+        if self
+            .method_map()
+            .get(&name)
+            .is_some_and(|method| method.as_function().flags().is_static())
+        {
+            self.method_map_mut().remove(&name);
+        }
+        self.static_methods().len() != len
+    }
+
     #[inline]
     pub fn add_property(&mut self, prop: PoolRef<Property>) {
         self.properties_mut().push(&prop);
@@ -372,6 +411,11 @@ impl Class {
         unsafe { mem::transmute(&mut self.0.funcs) }
     }
 
+    #[inline]
+    fn method_map_mut(&mut self) -> &mut RedHashMap<CName, &Method> {
+        unsafe { mem::transmute(&mut self.0.funcsByName) }
+    }
+
     #[inline]
     fn static_methods_mut(&mut self) -> &mut RedArray<&StaticMethod> {
         unsafe { mem::transmute(&mut self.0.staticFuncs) }
@@ -854,6 +898,23 @@ impl Function {
         unsafe { (self.0.returnType.cast::<Property>()).as_ref() }
     }
 
+    /// Returns whether the function has the same parameter and return types as another one.
+    fn has_signature_of(&self, other: &Function) -> bool {
+        let params = self.params();
+        let other_params = other.params();
+        let same_return = match (self.return_value(), other.return_value()) {
+            (Some(ret), Some(other_ret)) => ptr::eq(ret.type_(), other_ret.type_()),
+            (None, None) => true,
+            _ => false,
+        };
+        same_return
+            && params.len() == other_params.len()
+            && params
+                .iter()
+                .zip(other_params.iter())
+                .all(|(param, other_param)| ptr::eq(param.type_(), other_param.type_()))
+    }
+
     #[inline]
     pub fn add_param(&mut self, typ: CName, name: &CStr, is_out: bool, is_optional: bool) -> bool {
         unsafe {
@@ -867,6 +928,22 @@ impl Function {
         unsafe { self.0.SetReturnType(typ.to_raw()) };
     }
 
+    /// Returns the address of the native handler of the function.
+    /// Returns [`None`] if the function is not native.
+    pub fn native_handler(&self) -> Option<VoidPtr> {
+        if !self.flags().is_native() {
+            return None;
+        }
+        let handler = if self.parent().is_some() {
+            let method = unsafe { &*(self as *const _ as *const Method) };
+            method.native_handler()
+        } else {
+            let global = unsafe { &*(self as *const _ as *const GlobalFunction) };
+            global.native_handler()
+        };
+        (!handler.is_null()).then_some(handler)
+    }
+
     /// Executes the function with the given arguments represented as a tuple of values
     /// that satsify the [`NativeRepr`] trait.
     /// Returns the result of the function execution.
@@ -988,6 +1065,11 @@ impl GlobalFunction {
         };
     }
 
+    #[inline]
+    fn native_handler(&self) -> VoidPtr {
+        unsafe { mem::transmute_copy(&self.0.func) }
+    }
+
     #[inline]
     pub fn as_function(&self) -> &Function {
         unsafe { &*(self as *const _ as *const Function) }
@@ -1013,6 +1095,8 @@ impl Drop for GlobalFunction {
 pub struct Method(red::CClassFunction);
 
 impl Method {
+    /// Creates a new method of `class`. The handler receives the instances as `C`, so `class`
+    /// must be the class named [`ScriptClass::NAME`] or one of its subclasses.
     pub fn new<C, R>(
         full_name: &CStr,
         short_name: &CStr,
@@ -1027,11 +1111,6 @@ impl Method {
         let full_name = CNamePool::add_cstr(full_name);
         let short_name = CNamePool::add_cstr(short_name);
 
-        check_invariant(
-            CName::new(C::NAME) == class.name(),
-            "class name doesn't match",
-        );
-
         Self::ctor(
             func.as_mut_ptr(),
             class,
@@ -1067,6 +1146,11 @@ impl Method {
         };
     }
 
+    #[inline]
+    fn native_handler(&self) -> VoidPtr {
+        unsafe { mem::transmute_copy(&self.0.func) }
+    }
+
     #[inline]
     pub fn as_function(&self) -> &Function {
         unsafe { &*(self as *const _ as *const Function) }
@@ -1329,6 +1413,24 @@ impl Drop for ArrayType {
 pub struct Enum(red::CEnum);
 
 impl Enum {
+    /// Creates a new native enum with the given variant names and values, the size is given in
+    /// bytes. The returned handle can be registered with
+    /// [`RttiSystemMut::register_enum`](crate::RttiSystemMut::register_enum).
+    pub fn new_handle(name: &CStr, size: u8, variants: &[(&str, i64)]) -> EnumHandle {
+        let name = CNamePool::add_cstr(name);
+        let flags = red::CEnum_Flags::default();
+        let mut enum_ = Self(unsafe { red::CEnum::new(name.to_raw(), size.into(), flags) });
+        for &(variant, value) in variants {
+            let variant = CString::new(variant).expect("variant name should be valid");
+            enum_
+                .variant_names_mut()
+                .push(CNamePool::add_cstr(&variant));
+            enum_.variant_values_mut().push(value);
+        }
+        // like classes, enums are expected to live forever
+        EnumHandle(NonNull::from(Box::leak(Box::new(enum_))))
+    }
+
     #[inline]
     pub fn name(&self) -> CName {
         CName::from_raw(self.0.name)
@@ -1344,6 +1446,16 @@ impl Enum {
         unsafe { mem::transmute(&self.0.valueList) }
     }
 
+    #[inline]
+    fn variant_names_mut(&mut self) -> &mut RedArray<CName> {
+        unsafe { mem::transmute(&mut self.0.hashList) }
+    }
+
+    #[inline]
+    fn variant_values_mut(&mut self) -> &mut RedArray<i64> {
+        unsafe { mem::transmute(&mut self.0.valueList) }
+    }
+
     #[inline]
     pub fn byte_size(&self) -> u8 {
         self.0.actualSize
@@ -1368,12 +1480,57 @@ impl Drop for Enum {
     }
 }
 
+/// Enum handle to be used to register an enum with [`RttiSystemMut`](crate::RttiSystemMut).
+#[derive(Debug, Clone, Copy)]
+pub struct EnumHandle(NonNull<Enum>);
+
+impl EnumHandle {
+    #[inline]
+    pub(crate) fn as_ref(&self) -> &Enum {
+        unsafe { self.0.as_ref() }
+    }
+
+    #[inline]
+    pub(crate) fn as_mut(&mut self) -> &mut Enum {
+        unsafe { self.0.as_mut() }
+    }
+}
+
 /// An RTTI representation of a bitfield type.
 #[derive(Debug)]
 #[repr(transparent)]
 pub struct Bitfield(red::CBitfield);
 
 impl Bitfield {
+    /// Creates a new native bitfield with the given bit names and positions, the size is given
+    /// in bytes. Bits rejected by [`Bitfield::is_valid_bit`] and bit names containing nul bytes
+    /// are ignored. The returned handle can be registered with
+    /// [`RttiSystemMut::register_bitfield`](crate::RttiSystemMut::register_bitfield).
+    pub fn new_handle(name: &CStr, size: u8, bits: &[(&str, u8)]) -> BitfieldHandle {
+        let name = CNamePool::add_cstr(name);
+        let flags = red::CBitfield_Flags::default();
+        let mut bitfield = Self(unsafe { red::CBitfield::new(name.to_raw(), size.into(), flags) });
+        for &(bit_name, bit) in bits {
+            if !Self::is_valid_bit(size, bit) {
+                continue;
+            }
+            let Ok(bit_name) = CString::new(bit_name) else {
+                continue;
+            };
+            bitfield.0.bitNames[usize::from(bit)] = CNamePool::add_cstr(&bit_name).to_raw();
+            bitfield.0.validBits |= 1 << bit;
+        }
+        // like classes, bitfields are expected to live forever
+        BitfieldHandle(NonNull::from(Box::leak(Box::new(bitfield))))
+    }
+
+    /// Returns whether the bit fits in a bitfield of the given size in bytes. Bitfields can't
+    /// hold more than 64 bits.
+    #[inline]
+    pub fn is_valid_bit(size: u8, bit: u8) -> bool {
+        u32::from(bit) < u32::from(size.min(8)) * 8
+    }
+
     pub fn name(&self) -> CName {
         CName::from_raw(self.0.name)
     }
@@ -1403,6 +1560,22 @@ impl Drop for Bitfield {
     }
 }
 
+/// Bitfield handle to be used to register a bitfield with [`RttiSystemMut`](crate::RttiSystemMut).
+#[derive(Debug, Clone, Copy)]
+pub struct BitfieldHandle(NonNull<Bitfield>);
+
+impl BitfieldHandle {
+    #[inline]
+    pub(crate) fn as_ref(&self) -> &Bitfield {
+        unsafe { self.0.as_ref() }
+    }
+
+    #[inline]
+    pub(crate) fn as_mut(&mut self) -> &mut Bitfield {
+        unsafe { self.0.as_mut() }
+    }
+}
+
 /// The base type for all types held inside game references.
 #[derive(Debug)]
 #[repr(transparent)]
@@ -1486,6 +1659,28 @@ impl IScriptable {
         self.as_serializable().class()
     }
 
+    /// Returns the most-derived override of a method in the class of this instance if it's
+    /// defined in scripts. The method is looked up by its full name, and overrides are methods
+    /// of derived classes with the same short name and the same parameter and return types.
+    /// Returns [`None`] if the method is not overridden in scripts or does not exist.
+    pub fn get_script_override(&self, name: CName) -> Option<&Method> {
+        let class = self.class();
+        let method = class.get_method(name).ok()?;
+        // scripted overrides have mangled names, the methods are ordered from the most derived
+        // class
+        class
+            .base_iter_with_self()
+            .flat_map(Class::methods)
+            .copied()
+            .take_while(|&candidate| !ptr::eq(candidate, method))
+            .find(|candidate| {
+                let func = candidate.as_function();
+                !func.flags().is_native()
+                    && func.short_name() == method.as_function().short_name()
+                    && func.has_signature_of(method.as_function())
+            })
+    }
+
     #[inline]
     pub fn fields(&self) -> ValueContainer {
         ValueContainer(self.0.valueHolder)
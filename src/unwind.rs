@@ -0,0 +1,37 @@
+use std::any::Any;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+
+use crate::ENV;
+
+/// Runs a function, catching any panic that occurs. Panics are logged with the given name
+/// using [`SdkEnv::error`](crate::SdkEnv::error) and [`None`] is returned.
+///
+/// This is used at all boundaries where Rust code is called by the game, since a panic
+/// unwinding through an `extern "C"` function would abort the process.
+#[inline]
+pub fn catch_unwind<F, R>(name: impl fmt::Display, f: F) -> Option<R>
+where
+    F: FnOnce() -> R,
+{
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(res) => Some(res),
+        Err(payload) => {
+            report_panic(name, &*payload);
+            None
+        }
+    }
+}
+
+/// Logs a panic payload with the given name.
+#[cold]
+pub fn report_panic(name: impl fmt::Display, payload: &(dyn Any + Send)) {
+    let message = payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown panic payload");
+    if let Some(env) = ENV.get() {
+        env.error(format_args!("{name} panicked: {message}"));
+    }
+}